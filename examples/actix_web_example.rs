@@ -2,7 +2,6 @@ extern crate log;
 
 use actix_web::{get, web, App, HttpServer, Responder};
 use airbrake::*;
-use std::panic;
 
 #[get("/divide/{numerator}/{denominator}")]
 async fn divider(info: web::Path<(u32, u32)>) -> impl Responder {
@@ -18,7 +17,7 @@ async fn actix_main() -> std::io::Result<()> {
         .await
 }
 
-fn main() -> () {
+fn main() {
     env_logger::init();
 
     // Set up the client
@@ -31,6 +30,6 @@ fn main() -> () {
         .build()
         .expect("Failed to build config");
 
-    panic::set_hook(airbrake.panic_hook());
+    let _panic_hook = airbrake.install_panic_hook();
     let _ = actix_main();
 }
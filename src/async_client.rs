@@ -0,0 +1,190 @@
+use reqwest::Client;
+use serde::Serialize;
+use std::panic;
+use tokio::task;
+
+use crate::circuit_breaker::CircuitState;
+use crate::client::AirbrakeClientBuilderError;
+use crate::pipeline::{build_http_client, NoticePipeline};
+use crate::rate_limit::rate_limit_delay;
+use crate::{AirbrakeClientBuilder, AirbrakeClientError, Notice, NoticeBuilder, NoticeResponse};
+
+impl AirbrakeClientBuilder {
+    /// Builds a client that sends notices using non-blocking I/O. The
+    /// `workers` and `queue_size` settings don't apply to it, since the
+    /// caller's executor drives the requests.
+    ///
+    /// ```
+    /// use airbrake::AirbrakeClient;
+    ///
+    /// let airbrake = AirbrakeClient::builder()
+    ///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+    ///     .build_async()
+    ///     .unwrap();
+    /// ```
+    pub fn build_async(&self) -> Result<AsyncAirbrakeClient, AirbrakeClientBuilderError> {
+        Ok(AsyncAirbrakeClient {
+            client: self.async_http_client()?,
+            pipeline: NoticePipeline::new(self)?,
+        })
+    }
+
+    fn async_http_client(&self) -> Result<Client, AirbrakeClientBuilderError> {
+        build_http_client!(Client::builder(), self)
+    }
+}
+
+/// An Airbrake client for async services, sending notices with the async
+/// reqwest client instead of blocking the executor's threads.
+///
+/// ```no_run
+/// # async fn run() {
+/// use airbrake::AirbrakeClient;
+///
+/// let airbrake = AirbrakeClient::builder()
+///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+///     .build_async()
+///     .unwrap();
+///
+/// let err = std::io::Error::last_os_error();
+/// let notice = airbrake.new_notice_builder().add_error(err).build();
+/// airbrake.notify(notice).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncAirbrakeClient {
+    client: Client,
+    pipeline: NoticePipeline,
+}
+
+impl AsyncAirbrakeClient {
+    pub fn builder() -> AirbrakeClientBuilder {
+        AirbrakeClientBuilder::new()
+    }
+
+    fn endpoint_uri(&self) -> &str {
+        self.pipeline.endpoint_uri()
+    }
+
+    async fn send_request<T>(&self, payload: &T) -> Result<NoticeResponse, AirbrakeClientError>
+    where
+        T: Serialize,
+    {
+        // Send the request to the airbrake server, retrying transient failures
+        let mut attempts = self.pipeline.attempts();
+        loop {
+            let result = self.send_attempt(payload).await;
+            match attempts.backoff(&result) {
+                Some(backoff) => tokio::time::delay_for(backoff).await,
+                None => return attempts.finish(result),
+            }
+        }
+    }
+
+    async fn send_attempt<T>(&self, payload: &T) -> Result<NoticeResponse, AirbrakeClientError>
+    where
+        T: Serialize,
+    {
+        let response = match self
+            .client
+            .post(self.endpoint_uri())
+            .json(payload)
+            .send()
+            .await
+        {
+            Ok(response) => {
+                let status = response.status();
+                let delay = rate_limit_delay(response.headers());
+                response.text().await.map(|body| (status, delay, body))
+            }
+            Err(e) => Err(e),
+        };
+        self.pipeline.classify(response)
+    }
+
+    /// Notices built here carry the client's context, but aren't tied to
+    /// the client, so they have to be passed to `notify` rather than sent
+    /// with `Notice::send`.
+    pub fn new_notice_builder(&self) -> NoticeBuilder<'_> {
+        self.pipeline.new_notice_builder()
+    }
+
+    pub async fn notify(&self, notice: Notice<'_>) -> Result<NoticeResponse, AirbrakeClientError> {
+        self.pipeline.check()?;
+        let notice = self.pipeline.prepare(notice)?;
+        let notice = self.process_frames(notice.detach()).await;
+        self.pipeline.acquire()?;
+        let result = self.send_request(&notice).await;
+        self.pipeline.record(&result);
+        result
+    }
+
+    /// Resolves symbols and reads source files on tokio's blocking pool, so
+    /// they don't stall the executor
+    async fn process_frames(&self, mut notice: Notice<'static>) -> Notice<'static> {
+        let frames = self.pipeline.frames.clone();
+        let processed = task::spawn_blocking(move || {
+            frames.apply(&mut notice);
+            notice
+        })
+        .await;
+        match processed {
+            Ok(notice) => notice,
+            Err(e) => panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    /// Reports the state of the circuit breaker, which is always closed
+    /// when no breaker was configured
+    pub fn circuit_state(&self) -> CircuitState {
+        self.pipeline.circuit_state()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::AirbrakeClientBuilderError;
+    use crate::{AirbrakeClient, AirbrakeClientError, ContextProperties};
+
+    #[test]
+    fn build_async_validates_project() {
+        let client = AirbrakeClient::builder().project_id("foo").build_async();
+        assert_eq!(
+            client.unwrap_err(),
+            AirbrakeClientBuilderError::MissingProjectKey
+        )
+    }
+
+    #[test]
+    fn async_client_shares_builder_settings() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host("http://localhost:8080")
+            .environment("test")
+            .build_async()
+            .unwrap();
+        assert_eq!(
+            "http://localhost:8080/api/v3/projects/foo/notices?key=bar",
+            client.endpoint_uri()
+        );
+        let notice = client.new_notice_builder().build();
+        assert_eq!(
+            Some("test".to_string()),
+            notice.context.and_then(|c| c.environment)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn notify_reports_connection_errors() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host("http://127.0.0.1:1")
+            .build_async()
+            .unwrap();
+        let notice = client.new_notice_builder().build();
+        match client.notify(notice).await {
+            Err(AirbrakeClientError::ReqwestError(_)) => (),
+            other => panic!("expected ReqwestError, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::AirbrakeClientError;
+
+/// The state of a client's circuit breaker, exposed for health checks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CircuitState {
+    /// Notices are sent normally
+    Closed,
+    /// The Airbrake host has been failing, notices are rejected without
+    /// being sent until the open duration passes
+    Open,
+    /// The open duration has passed and a single probe notice is allowed
+    /// through to find out whether the host has recovered
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// Stops sending notices after a run of consecutive failures, so callers
+/// fail fast instead of waiting for a timeout while the Airbrake host is
+/// down. Clones share the same state.
+#[derive(Debug, Clone)]
+pub(crate) struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(failure_threshold: u32, open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            state: Arc::new(Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn open_elapsed(&self, state: &BreakerState) -> bool {
+        state
+            .opened_at
+            .map_or(true, |opened_at| opened_at.elapsed() >= self.open_duration)
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        let state = self.lock();
+        match state.state {
+            CircuitState::Open if self.open_elapsed(&state) => CircuitState::HalfOpen,
+            current => current,
+        }
+    }
+
+    /// Fails fast while the breaker is open, without claiming the probe
+    pub(crate) fn check(&self) -> Result<(), AirbrakeClientError> {
+        match self.state() {
+            CircuitState::Open => Err(AirbrakeClientError::CircuitOpen),
+            _ => Ok(()),
+        }
+    }
+
+    /// Asks for permission to send a request. Once the open duration has
+    /// passed only one request at a time is let through as a probe.
+    pub(crate) fn acquire(&self) -> Result<(), AirbrakeClientError> {
+        let mut state = self.lock();
+        match state.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open if !self.open_elapsed(&state) => {
+                Err(AirbrakeClientError::CircuitOpen)
+            }
+            _ if state.probe_in_flight => Err(AirbrakeClientError::CircuitOpen),
+            _ => {
+                debug!("Airbrake circuit breaker half-open, sending probe");
+                state.state = CircuitState::HalfOpen;
+                state.probe_in_flight = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Records the outcome of a request let through by `acquire`. Only
+    /// failures that suggest the host is unavailable count against it.
+    pub(crate) fn record<T>(&self, result: &Result<T, AirbrakeClientError>) {
+        let mut state = self.lock();
+        state.probe_in_flight = false;
+        let failed = matches!(
+            result,
+            Err(AirbrakeClientError::ReqwestError(_))
+                | Err(AirbrakeClientError::ServerError { .. })
+        );
+        if !failed {
+            state.state = CircuitState::Closed;
+            state.failures = 0;
+            state.opened_at = None;
+            return;
+        }
+
+        state.failures += 1;
+        if state.state == CircuitState::HalfOpen || state.failures >= self.failure_threshold {
+            warn!(
+                "Airbrake circuit breaker opened after {} failures",
+                state.failures
+            );
+            state.state = CircuitState::Open;
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitBreaker, CircuitState};
+    use crate::AirbrakeClientError;
+    use std::time::Duration;
+
+    fn server_error() -> Result<(), AirbrakeClientError> {
+        Err(AirbrakeClientError::ServerError {
+            status: 503,
+            message: String::new(),
+        })
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record(&server_error());
+        assert_eq!(CircuitState::Closed, breaker.state());
+        breaker.record(&server_error());
+        assert_eq!(CircuitState::Open, breaker.state());
+
+        match breaker.acquire() {
+            Err(AirbrakeClientError::CircuitOpen) => (),
+            other => panic!("expected CircuitOpen, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn client_errors_do_not_count_as_failures() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker.record::<()>(&Err(AirbrakeClientError::Unauthorized {
+            status: 401,
+            message: String::new(),
+        }));
+        assert_eq!(CircuitState::Closed, breaker.state());
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record(&server_error());
+        breaker.record(&Ok(()));
+        breaker.record(&server_error());
+        assert_eq!(CircuitState::Closed, breaker.state());
+    }
+
+    #[test]
+    fn half_open_allows_a_single_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(0));
+        breaker.record(&server_error());
+        assert_eq!(CircuitState::HalfOpen, breaker.state());
+
+        assert!(breaker.acquire().is_ok());
+        assert!(breaker.acquire().is_err());
+
+        breaker.record(&Ok(()));
+        assert_eq!(CircuitState::Closed, breaker.state());
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[test]
+    fn failed_probe_reopens_breaker() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        for _ in 0..3 {
+            breaker.record(&server_error());
+        }
+        // Pretend the open duration has passed
+        breaker.lock().opened_at = None;
+        assert!(breaker.acquire().is_ok());
+        breaker.record(&server_error());
+        assert_eq!(CircuitState::Open, breaker.state());
+    }
+}
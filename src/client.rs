@@ -1,20 +1,40 @@
 use log::warn;
 use reqwest::blocking::Client;
+use reqwest::Proxy;
 use serde::Serialize;
 use std::env;
 use std::marker::{Send, Sync};
-use std::panic::PanicInfo;
-use std::time::Instant;
+use std::panic::{self, PanicHookInfo};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
 
+use crate::circuit_breaker::{CircuitBreaker, CircuitState};
+use crate::filter::NoticeFilters;
+use crate::frames::FrameProcessing;
+use crate::panic::{panic_context, PanicHook, PanicHookGuard};
+use crate::pipeline::{build_http_client, NoticePipeline};
+use crate::proxy::{build_proxy, parse_no_proxy};
+use crate::queue::NoticeQueue;
+use crate::rate_limit::rate_limit_delay;
+use crate::redaction::KeyRedaction;
+use crate::retry::RetryPolicy;
+use crate::FilterDecision;
+use crate::KeyPattern;
 use crate::Notice;
 use crate::NoticeBuilder;
 use crate::NoticeError;
-use crate::{Context, ContextBuilder, ContextProperties};
+use crate::NoticeFrame;
+use crate::NoticeResponse;
+use crate::{ContextBuilder, ContextProperties};
 
 const DEFAULT_HOSTNAME: &str = "https://app.airbrake.io";
 const ENV_VAR_PROJECT_ID: &str = "AIRBRAKE_PROJECT_ID";
 const ENV_VAR_PROJECT_KEY: &str = "AIRBRAKE_API_KEY";
 const ENV_VAR_HOST: &str = "AIRBRAKE_HOST";
+const DEFAULT_WORKERS: usize = 1;
+const DEFAULT_QUEUE_SIZE: usize = 100;
+const DEFAULT_PANIC_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Debug, PartialEq)]
 pub enum AirbrakeClientBuilderError {
@@ -22,6 +42,9 @@ pub enum AirbrakeClientBuilderError {
     MissingProjectKey,
     EmptyProjectId,
     EmptyProjectKey,
+    InvalidProxy(String),
+    HttpClient(String),
+    WorkerThread(String),
 }
 
 #[derive(Default)]
@@ -30,7 +53,20 @@ pub struct AirbrakeClientBuilder {
     pub project_key: Option<String>,
     pub host: Option<String>,
     pub proxy: Option<String>,
+    pub proxy_auth: Option<(String, String)>,
+    pub no_proxy: Vec<String>,
     pub context: Option<ContextBuilder>,
+    pub workers: Option<usize>,
+    pub queue_size: Option<usize>,
+    pub panic_flush_timeout: Option<Duration>,
+    pub(crate) filters: NoticeFilters,
+    pub(crate) redaction: KeyRedaction,
+    pub(crate) frames: FrameProcessing,
+    pub(crate) circuit_breaker: Option<(u32, Duration)>,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub max_retries: Option<u32>,
+    pub retry_backoff: Option<(Duration, Duration)>,
 }
 
 impl AirbrakeClientBuilder {
@@ -137,18 +173,274 @@ impl AirbrakeClientBuilder {
         self
     }
 
+    /// Routes notices through a proxy. The proxy may use the http, https
+    /// or (with the `socks` feature) socks5 scheme, and defaults to http
+    /// when no scheme is given.
     pub fn proxy(&mut self, proxy: &str) -> &mut AirbrakeClientBuilder {
         self.proxy = Some(proxy.to_string());
         self
     }
 
+    /// Sets the credentials used to authenticate with the proxy
+    pub fn proxy_auth(&mut self, username: &str, password: &str) -> &mut AirbrakeClientBuilder {
+        self.proxy_auth = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Excludes hosts from being proxied, using the same comma separated
+    /// format as the `NO_PROXY` environment variable. A domain excludes
+    /// itself and all of its subdomains, and `*` excludes every host.
+    pub fn no_proxy(&mut self, no_proxy: &str) -> &mut AirbrakeClientBuilder {
+        self.no_proxy.extend(parse_no_proxy(no_proxy));
+        self
+    }
+
+    /// Sets the number of background threads delivering notices queued by
+    /// `notify`. Setting it to zero disables the queue, making `notify`
+    /// deliver notices on the calling thread.
+    pub fn workers(&mut self, workers: usize) -> &mut AirbrakeClientBuilder {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Sets how many notices can wait for a worker before `notify` starts
+    /// rejecting them with `AirbrakeClientError::QueueFull`. Zero counts
+    /// as one, so a notice never has to wait for a worker to be idle.
+    pub fn queue_size(&mut self, queue_size: usize) -> &mut AirbrakeClientBuilder {
+        self.queue_size = Some(queue_size);
+        self
+    }
+
+    /// Sets how many lines of source code around each backtrace frame are
+    /// sent along with it, defaults to 2. Code is only read for frames in
+    /// the project, when the file is found on disk. Zero disables it.
+    pub fn code_hunks(&mut self, radius: usize) -> &mut AirbrakeClientBuilder {
+        self.frames.code_radius = radius;
+        self
+    }
+
+    /// Whether frames of the notifier and of the backtrace capture are
+    /// left out of backtraces, along with the frames above the point the
+    /// backtrace was captured at. Enabled by default.
+    pub fn strip_notifier_frames(&mut self, strip: bool) -> &mut AirbrakeClientBuilder {
+        self.frames.strip_notifier_frames = strip;
+        self
+    }
+
+    /// Whether frames of `std`, `core`, `alloc` and the runtime that starts
+    /// the program are left out of backtraces. Enabled by default.
+    pub fn strip_std_frames(&mut self, strip: bool) -> &mut AirbrakeClientBuilder {
+        self.frames.strip_std_frames = strip;
+        self
+    }
+
+    /// Whether backtrace file paths are rewritten so they are the same on
+    /// every build machine, enabled by default. The root directory is
+    /// replaced with `/PROJECT_ROOT`, the cargo registry with
+    /// `/CARGO_REGISTRY` and the rust sysroot with `/RUSTLIB`.
+    pub fn normalize_paths(&mut self, normalize: bool) -> &mut AirbrakeClientBuilder {
+        self.frames.normalize_paths = normalize;
+        self
+    }
+
+    /// Whether generic arguments are left out of backtrace function names,
+    /// so `core::result::Result<T,E>::map_err` is sent as
+    /// `core::result::Result::map_err`. Disabled by default.
+    pub fn simplify_generics(&mut self, simplify: bool) -> &mut AirbrakeClientBuilder {
+        self.frames.simplify_generics = simplify;
+        self
+    }
+
+    /// Caps the number of frames resolved for backtraces captured with
+    /// `Backtrace::new_unresolved()`, defaults to 128
+    pub fn max_backtrace_frames(&mut self, max_frames: usize) -> &mut AirbrakeClientBuilder {
+        self.frames.max_frames = max_frames;
+        self
+    }
+
+    /// Leaves the backtrace frames the predicate returns `true` for out of
+    /// every notice. A backtrace is sent unfiltered rather than empty.
+    /// Frames have `in_project` filled in by the time the predicate runs.
+    ///
+    /// ```
+    /// use airbrake::AirbrakeClient;
+    ///
+    /// let airbrake = AirbrakeClient::builder()
+    ///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+    ///     .drop_frames(|frame| {
+    ///         frame
+    ///             .function
+    ///             .as_ref()
+    ///             .map_or(false, |function| function.starts_with("tokio::"))
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn drop_frames<F>(&mut self, predicate: F) -> &mut AirbrakeClientBuilder
+    where
+        F: Fn(&NoticeFrame) -> bool + Send + Sync + 'static,
+    {
+        self.frames.frame_filters.push(Arc::new(predicate));
+        self
+    }
+
+    /// Sets how long the panic hook waits for its notice to reach Airbrake
+    /// before letting the panic continue, so a panic that ends the process
+    /// doesn't exit before the notice is sent. Defaults to two seconds.
+    pub fn flush_on_panic(&mut self, timeout: Duration) -> &mut AirbrakeClientBuilder {
+        self.panic_flush_timeout = Some(timeout);
+        self
+    }
+
+    /// Registers a filter that runs over every notice before it is sent.
+    /// Filters run in the order they were added and can modify the notice
+    /// or drop it, in which case `notify` returns
+    /// `AirbrakeClientError::NoticeDropped` with the given reason.
+    ///
+    /// ```
+    /// use airbrake::{AirbrakeClient, FilterDecision};
+    ///
+    /// let airbrake = AirbrakeClient::builder()
+    ///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+    ///     .add_filter(|notice| {
+    ///         if notice.errors.iter().any(|e| e.name == "Timeout") {
+    ///             return FilterDecision::Drop("timeouts are expected".into());
+    ///         }
+    ///         notice.errors.retain(|e| e.name != "Ignored");
+    ///         FilterDecision::Keep
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn add_filter<F>(&mut self, filter: F) -> &mut AirbrakeClientBuilder
+    where
+        F: Fn(&mut Notice<'_>) -> FilterDecision + Send + Sync + 'static,
+    {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Replaces the values of matching params, session and environment keys,
+    /// as well as matching query parameters in the context url, with
+    /// `[Filtered]` before notices are sent. Keys can be matched by exact
+    /// name or by regular expression.
+    ///
+    /// ```
+    /// use airbrake::AirbrakeClient;
+    /// use regex::Regex;
+    ///
+    /// let airbrake = AirbrakeClient::builder()
+    ///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+    ///     .blocklist_key("password")
+    ///     .blocklist_key(Regex::new("(?i)token").unwrap())
+    ///     .build();
+    /// ```
+    pub fn blocklist_key<K: Into<KeyPattern>>(&mut self, key: K) -> &mut AirbrakeClientBuilder {
+        self.redaction.blocklist.push(key.into());
+        self
+    }
+
+    /// Once any allowlisted keys are configured, the values of every key that
+    /// doesn't match one of them are replaced with `[Filtered]`
+    pub fn allowlist_key<K: Into<KeyPattern>>(&mut self, key: K) -> &mut AirbrakeClientBuilder {
+        self.redaction.allowlist.push(key.into());
+        self
+    }
+
+    /// Enables a circuit breaker that opens after `failure_threshold`
+    /// consecutive connection errors or 5xx responses. While it is open,
+    /// notices fail immediately with `AirbrakeClientError::CircuitOpen`.
+    /// Once `open_duration` has passed a single probe notice is let
+    /// through, closing the breaker again if it succeeds.
+    ///
+    /// ```
+    /// use airbrake::AirbrakeClient;
+    /// use std::time::Duration;
+    ///
+    /// let airbrake = AirbrakeClient::builder()
+    ///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+    ///     .circuit_breaker(5, Duration::from_secs(30))
+    ///     .build();
+    /// ```
+    pub fn circuit_breaker(
+        &mut self,
+        failure_threshold: u32,
+        open_duration: Duration,
+    ) -> &mut AirbrakeClientBuilder {
+        self.circuit_breaker = Some((failure_threshold, open_duration));
+        self
+    }
+
+    /// Sets the total time allowed for a single request to Airbrake,
+    /// from connecting until the response body has been read
+    pub fn timeout(&mut self, timeout: Duration) -> &mut AirbrakeClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the time allowed for establishing a connection to Airbrake
+    pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut AirbrakeClientBuilder {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Sets how many times a notice is resent after a connection error,
+    /// timeout or 5xx response. Notices aren't retried by default.
+    ///
+    /// ```
+    /// use airbrake::AirbrakeClient;
+    /// use std::time::Duration;
+    ///
+    /// let airbrake = AirbrakeClient::builder()
+    ///     .project("113743", "81bbff95d52f8856c770bb39e827f3f6")
+    ///     .timeout(Duration::from_secs(5))
+    ///     .max_retries(3)
+    ///     .retry_backoff(Duration::from_millis(200), Duration::from_secs(5))
+    ///     .build();
+    /// ```
+    pub fn max_retries(&mut self, max_retries: u32) -> &mut AirbrakeClientBuilder {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Sets the backoff before the first retry, which doubles with every
+    /// further retry up to `max`. Each backoff is randomised between half
+    /// and all of its value.
+    pub fn retry_backoff(
+        &mut self,
+        initial: Duration,
+        max: Duration,
+    ) -> &mut AirbrakeClientBuilder {
+        self.retry_backoff = Some((initial, max));
+        self
+    }
+
+    pub(crate) fn retry_policy(&self) -> RetryPolicy {
+        let mut policy = RetryPolicy::default();
+        if let Some(max_retries) = self.max_retries {
+            policy.max_retries = max_retries;
+        }
+        if let Some((initial, max)) = self.retry_backoff {
+            policy.initial_backoff = initial;
+            policy.max_backoff = max.max(initial);
+        }
+        policy
+    }
+
+    pub(crate) fn circuit_breaker_settings(&self) -> Option<CircuitBreaker> {
+        self.circuit_breaker
+            .map(|(failure_threshold, open_duration)| {
+                CircuitBreaker::new(failure_threshold, open_duration)
+            })
+    }
+
     // Sets the configurations context to an existing context builder
     pub fn context(&mut self, context: ContextBuilder) -> &mut AirbrakeClientBuilder {
         self.context = Some(context);
         self
     }
 
-    pub fn build(&self) -> Result<AirbrakeClient, AirbrakeClientBuilderError> {
+    /// Validates the project settings shared by every client flavour and
+    /// returns the project id and key
+    pub(crate) fn project_settings(&self) -> Result<(String, String), AirbrakeClientBuilderError> {
         let project_id = match &self.project_id {
             Some(id) => id,
             None => return Err(AirbrakeClientBuilderError::MissingProjectId),
@@ -163,19 +455,43 @@ impl AirbrakeClientBuilder {
         if project_key.is_empty() {
             return Err(AirbrakeClientBuilderError::EmptyProjectKey);
         }
-        let context = self.context.clone().map(|c| c.build());
-
-        Ok(AirbrakeClient {
-            client: Client::new(),
-            project_id: project_id.to_string(),
-            project_key: project_key.to_string(),
-            host: self
-                .host
-                .clone()
-                .unwrap_or_else(|| DEFAULT_HOSTNAME.to_owned()),
-            proxy: self.proxy.clone(),
-            context,
-        })
+        Ok((project_id.to_string(), project_key.to_string()))
+    }
+
+    pub(crate) fn host_or_default(&self) -> String {
+        self.host
+            .clone()
+            .unwrap_or_else(|| DEFAULT_HOSTNAME.to_owned())
+    }
+
+    pub(crate) fn proxy_settings(&self) -> Result<Option<Proxy>, AirbrakeClientBuilderError> {
+        match &self.proxy {
+            Some(proxy) => build_proxy(proxy, self.proxy_auth.as_ref(), &self.no_proxy).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn http_client(&self) -> Result<Client, AirbrakeClientBuilderError> {
+        build_http_client!(Client::builder(), self)
+    }
+
+    pub fn build(&self) -> Result<AirbrakeClient, AirbrakeClientBuilderError> {
+        let mut client = AirbrakeClient {
+            client: self.http_client()?,
+            pipeline: NoticePipeline::new(self)?,
+            panic_flush_timeout: self
+                .panic_flush_timeout
+                .unwrap_or(DEFAULT_PANIC_FLUSH_TIMEOUT),
+            queue: None,
+        };
+        let workers = self.workers.unwrap_or(DEFAULT_WORKERS);
+        if workers > 0 {
+            let queue_size = self.queue_size.unwrap_or(DEFAULT_QUEUE_SIZE).max(1);
+            let queue = NoticeQueue::start(&client, workers, queue_size)
+                .map_err(|e| AirbrakeClientBuilderError::WorkerThread(e.to_string()))?;
+            client.queue = Some(Arc::new(queue));
+        }
+        Ok(client)
     }
 }
 
@@ -193,6 +509,17 @@ impl ContextProperties for AirbrakeClientBuilder {
 pub enum AirbrakeClientError {
     ReqwestError(reqwest::Error),
     NoticeClientNotSet,
+    QueueFull,
+    QueueClosed,
+    NoticeDropped(String),
+    InvalidResponse(String),
+    BadRequest { status: u16, message: String },
+    Unauthorized { status: u16, message: String },
+    TooManyRequests { status: u16, message: String },
+    ServerError { status: u16, message: String },
+    UnexpectedStatus { status: u16, message: String },
+    RateLimited { retry_after: Duration },
+    CircuitOpen,
 }
 
 impl From<reqwest::Error> for AirbrakeClientError {
@@ -201,14 +528,19 @@ impl From<reqwest::Error> for AirbrakeClientError {
     }
 }
 
+pub(crate) fn endpoint_uri(host: &str, project_id: &str, project_key: &str) -> String {
+    format!(
+        "{}/api/v3/projects/{}/notices?key={}",
+        host, project_id, project_key,
+    )
+}
+
 #[derive(Debug, Clone)]
 pub struct AirbrakeClient {
     client: Client,
-    project_id: String,
-    project_key: String,
-    host: String,
-    proxy: Option<String>,
-    context: Option<Context>,
+    pipeline: NoticePipeline,
+    panic_flush_timeout: Duration,
+    queue: Option<Arc<NoticeQueue>>,
 }
 
 impl AirbrakeClient {
@@ -216,74 +548,192 @@ impl AirbrakeClient {
         AirbrakeClientBuilder::new()
     }
 
-    fn endpoint_uri(&self) -> String {
-        format!(
-            "{}/api/v3/projects/{}/notices?key={}",
-            self.host, self.project_id, self.project_key,
-        )
+    fn endpoint_uri(&self) -> &str {
+        self.pipeline.endpoint_uri()
     }
 
-    fn send_request<T>(&self, uri: &str, payload: &T) -> Result<(), AirbrakeClientError>
+    fn send_request<T>(&self, payload: &T) -> Result<NoticeResponse, AirbrakeClientError>
     where
         T: Serialize,
     {
-        // Prepare a duration timer to track how long it takes to send the request.
-        let start_time = Instant::now();
+        // Send the request to the airbrake server, retrying transient failures
+        let mut attempts = self.pipeline.attempts();
+        loop {
+            let result = self.send_attempt(payload);
+            match attempts.backoff(&result) {
+                Some(backoff) => thread::sleep(backoff),
+                None => return attempts.finish(result),
+            }
+        }
+    }
 
-        // Now send the request to the airbrake server
-        let response = self.client.post(uri).json(payload).send();
+    fn send_attempt<T>(&self, payload: &T) -> Result<NoticeResponse, AirbrakeClientError>
+    where
+        T: Serialize,
+    {
+        let response = self
+            .client
+            .post(self.endpoint_uri())
+            .json(payload)
+            .send()
+            .and_then(|response| {
+                let status = response.status();
+                let delay = rate_limit_delay(response.headers());
+                response.text().map(|body| (status, delay, body))
+            });
+        self.pipeline.classify(response)
+    }
 
-        // Calculate send duration and print it to debug
-        let duration = start_time.elapsed();
-        debug!("Airbrake notify request took: {:?}", duration);
+    pub fn new_notice_builder(&self) -> NoticeBuilder<'_> {
+        let mut notice_builder = self.pipeline.new_notice_builder();
+        notice_builder.set_client(self);
+        notice_builder
+    }
 
-        // Return the response, ignoring the content if it's successful
-        response.map(|_| ()).map_err(|e| {
-            warn!("Airbrake notification failed");
-            AirbrakeClientError::from(e)
-        })
+    /// Queues the notice for delivery by a background worker and returns
+    /// immediately. When the client was built with zero workers the notice
+    /// is sent on the calling thread instead.
+    ///
+    /// After Airbrake responds with a rate limit, notices are rejected with
+    /// `AirbrakeClientError::RateLimited` until the requested delay passes.
+    pub fn notify(&self, notice: Notice) -> Result<(), AirbrakeClientError> {
+        self.pipeline.check()?;
+        let notice = self.pipeline.prepare(notice)?;
+        match &self.queue {
+            Some(queue) => queue.push(notice.detach()),
+            None => self.deliver(notice).map(|_| ()),
+        }
     }
 
-    pub fn new_notice_builder(&self) -> NoticeBuilder {
-        let mut notice_builder = match &self.context {
-            Some(context) => context.new_notice_builder(),
-            None => Notice::builder(),
-        };
-        notice_builder.set_client(&self);
-        notice_builder
+    /// Sends the notice on the calling thread, blocking until the request
+    /// to Airbrake completes, and returns the notice Airbrake created
+    pub fn notify_sync(&self, notice: Notice) -> Result<NoticeResponse, AirbrakeClientError> {
+        let notice = self.pipeline.prepare(notice)?;
+        self.deliver(notice)
     }
 
-    pub fn notify(&self, mut notice: Notice) -> Result<(), AirbrakeClientError> {
-        // TODO: This is a codesmell- the notify function shouldn't be
-        // mutating the notice. Testing this is very difficult. Too
-        // difficult for me to bother figuring out, which means this is
-        // poorly designed
-        notice.context = notice.context.or_else(|| self.context.clone());
-        let endpoint = self.endpoint_uri();
-        self.send_request(&endpoint, &notice)
+    /// Blocks until every notice queued by `notify` has been delivered, or
+    /// the timeout passes. Returns whether all notices were delivered in
+    /// time.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let airbrake = airbrake::configure(|config| {
+    ///     config.project_id("113743");
+    ///     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+    /// });
+    /// // ... just before the process exits
+    /// airbrake.close(Duration::from_secs(2));
+    /// ```
+    pub fn flush(&self, timeout: Duration) -> bool {
+        self.queue
+            .as_ref()
+            .map_or(true, |queue| queue.flush(timeout))
+    }
+
+    /// Stops the background workers from accepting new notices, after
+    /// which `notify` fails with `AirbrakeClientError::QueueClosed`, then
+    /// flushes the notices that were already queued. Closing affects every
+    /// copy of the client.
+    pub fn close(&self, timeout: Duration) -> bool {
+        if let Some(queue) = &self.queue {
+            queue.close();
+        }
+        self.flush(timeout)
+    }
+
+    /// Sends an already prepared notice to Airbrake, after adding source
+    /// code to its backtraces
+    pub(crate) fn deliver(
+        &self,
+        mut notice: Notice,
+    ) -> Result<NoticeResponse, AirbrakeClientError> {
+        self.pipeline.frames.apply(&mut notice);
+        self.pipeline.acquire()?;
+        let result = self.send_request(&notice);
+        self.pipeline.record(&result);
+        result
+    }
+
+    /// Reports the state of the circuit breaker, which is always closed
+    /// when no breaker was configured
+    pub fn circuit_state(&self) -> CircuitState {
+        self.pipeline.circuit_state()
     }
 
     /// This function returns a closure that can be passed to the `panic::set_hook`
     /// function. Only a single panic hook can be set at once, so exposing functionality
     /// this way forces you to manage your panic hooks yourself.
-    pub fn panic_hook(&self) -> Box<dyn Fn(&PanicInfo<'_>) + Send + Sync + 'static> {
+    ///
+    /// The panic's location and the name and id of the panicking thread are
+    /// sent along as context.
+    pub fn panic_hook(&self) -> Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static> {
         let airbrake_client = self.clone();
-        Box::new(move |panic_info: &PanicInfo<'_>| {
+        Box::new(move |panic_info: &PanicHookInfo<'_>| {
             let panic_backtrace = backtrace::Backtrace::new();
             let notice_error = NoticeError::from_panic_backtrace(panic_info, &panic_backtrace);
-            let _ = airbrake_client
-                .new_notice_builder()
-                .add_notice(notice_error)
-                .build()
-                .send();
+            let mut notice = airbrake_client.new_notice_builder();
+            notice.add_notice(notice_error);
+            notice
+                .context
+                .get_or_insert_with(ContextBuilder::new)
+                .merge(&panic_context(panic_info));
+            airbrake_client
+                .notify_with_timeout(notice.build(), airbrake_client.panic_flush_timeout);
         })
     }
+
+    /// Sends the notice from a separate thread and waits for it to be
+    /// delivered, giving up after the timeout
+    fn notify_with_timeout(&self, notice: Notice, timeout: Duration) {
+        let notice = match self.pipeline.prepare(notice) {
+            Ok(notice) => notice.detach(),
+            Err(_) => return,
+        };
+        let client = self.clone();
+        let (sender, receiver) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("airbrake-panic".to_string())
+            .spawn(move || {
+                let _ = sender.send(client.deliver(notice));
+            });
+        if spawned.is_ok() && receiver.recv_timeout(timeout).is_err() {
+            warn!(
+                "Airbrake panic notice wasn't delivered within {:?}",
+                timeout
+            );
+        }
+    }
+
+    /// Installs `panic_hook` in front of the currently installed panic hook,
+    /// which still runs after the panic has been reported, so the default
+    /// message on stderr isn't lost. The previous hook is restored when the
+    /// returned guard is dropped.
+    ///
+    /// ```
+    /// let airbrake = airbrake::configure(|config| {
+    ///     config.project_id("113743");
+    ///     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+    /// });
+    /// let _guard = airbrake.install_panic_hook();
+    /// ```
+    pub fn install_panic_hook(&self) -> PanicHookGuard {
+        let previous: Arc<PanicHook> = Arc::from(panic::take_hook());
+        let airbrake_hook = self.panic_hook();
+        let chained = Arc::clone(&previous);
+        panic::set_hook(Box::new(move |panic_info: &PanicHookInfo<'_>| {
+            airbrake_hook(panic_info);
+            chained(panic_info);
+        }));
+        PanicHookGuard::new(previous)
+    }
 }
 
 #[cfg(test)]
 mod context_passthrough_tests {
     use crate::AirbrakeClient;
-    use crate::ContextProperties;
+    use crate::{ContextProperties, Severity};
     use serde_json::{self, Value};
     use std::str::FromStr;
 
@@ -294,7 +744,7 @@ mod context_passthrough_tests {
             .project_key("bar")
             .operating_system("SolarOS")
             .version("0.0.0")
-            .severity("critical")
+            .severity(Severity::Critical)
             .build()
             .unwrap();
         let notice = client.new_notice_builder().build();
@@ -330,7 +780,10 @@ mod context_passthrough_tests {
             .component("controller")
             .build()
             .unwrap();
-        let notice = client.new_notice_builder().severity("warning").build();
+        let notice = client
+            .new_notice_builder()
+            .severity(Severity::Warning)
+            .build();
 
         let expected_json = r#"
         {
@@ -355,11 +808,459 @@ mod context_passthrough_tests {
     }
 }
 
+#[cfg(test)]
+mod filter_tests {
+    use crate::{AirbrakeClient, AirbrakeClientError, ContextProperties, FilterDecision, Notice};
+
+    #[test]
+    fn dropped_notice_reports_filter_reason() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .add_filter(|_| FilterDecision::Drop("ignored in tests".to_string()))
+            .build()
+            .unwrap();
+        let result = client.new_notice_builder().build().send();
+        match result {
+            Err(AirbrakeClientError::NoticeDropped(reason)) => {
+                assert_eq!("ignored in tests", reason)
+            }
+            other => panic!("expected NoticeDropped, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn filters_see_client_context() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .environment("test")
+            .add_filter(|notice| match &notice.context {
+                Some(context) if context.environment.as_deref() == Some("test") => {
+                    FilterDecision::Drop("test environment".to_string())
+                }
+                _ => FilterDecision::Keep,
+            })
+            .build()
+            .unwrap();
+        let notice = Notice::builder().build();
+        match client.notify_sync(notice) {
+            Err(AirbrakeClientError::NoticeDropped(_)) => (),
+            other => panic!("expected NoticeDropped, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod redaction_tests {
+    use crate::{AirbrakeClient, FilterDecision};
+
+    #[test]
+    fn blocklisted_keys_are_redacted_after_filters() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .blocklist_key("password")
+            .add_filter(|notice| {
+                notice
+                    .params
+                    .get_or_insert_with(Default::default)
+                    .insert("password".to_string(), "added-by-filter".into());
+                FilterDecision::Keep
+            })
+            .build()
+            .unwrap();
+        let notice = client
+            .pipeline
+            .prepare(client.new_notice_builder().build())
+            .unwrap();
+
+        assert_eq!("[Filtered]", notice.params.unwrap()["password"]);
+    }
+}
+
+#[cfg(test)]
+mod response_tests {
+    use crate::test_server::TestServer;
+    use crate::{AirbrakeClient, AirbrakeClientError, Notice, NoticeResponse};
+
+    #[test]
+    fn notify_sync_returns_created_notice() {
+        let server = TestServer::start(vec![(
+            "201 Created",
+            "",
+            r#"{"id": "1", "url": "https://airbrake.io/locate/1"}"#,
+        )]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .build()
+            .unwrap();
+        let response = client.notify_sync(Notice::builder().build()).unwrap();
+
+        assert_eq!(
+            NoticeResponse {
+                id: "1".to_string(),
+                url: "https://airbrake.io/locate/1".to_string()
+            },
+            response
+        );
+        assert_eq!(1, server.requests());
+    }
+
+    #[test]
+    fn notify_sync_reports_rejected_notices() {
+        let server = TestServer::start(vec![(
+            "401 Unauthorized",
+            "",
+            r#"{"message": "invalid project key"}"#,
+        )]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .build()
+            .unwrap();
+        match client.notify_sync(Notice::builder().build()) {
+            Err(AirbrakeClientError::Unauthorized { status, message }) => {
+                assert_eq!(401, status);
+                assert_eq!("invalid project key", message);
+            }
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use crate::test_server::TestServer;
+    use crate::{AirbrakeClient, AirbrakeClientError, Notice};
+    use std::time::Duration;
+
+    #[test]
+    fn rate_limited_client_stops_sending() {
+        let server = TestServer::start(vec![(
+            "429 Too Many Requests",
+            "X-RateLimit-Delay: 30\r\n",
+            r#"{"message": "IP is rate limited"}"#,
+        )]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .build()
+            .unwrap();
+        match client.notify_sync(Notice::builder().build()) {
+            Err(AirbrakeClientError::RateLimited { retry_after }) => {
+                assert_eq!(Duration::from_secs(30), retry_after)
+            }
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+
+        // Neither the client nor its clones reach the server again
+        let clone = client.clone();
+        match clone.notify(Notice::builder().build()) {
+            Err(AirbrakeClientError::RateLimited { .. }) => (),
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+        assert!(client.notify_sync(Notice::builder().build()).is_err());
+        assert_eq!(1, server.requests());
+    }
+}
+
+#[cfg(test)]
+mod circuit_breaker_tests {
+    use crate::test_server::TestServer;
+    use crate::{AirbrakeClient, AirbrakeClientError, CircuitState, Notice};
+    use std::time::Duration;
+
+    #[test]
+    fn failing_host_opens_circuit() {
+        let server = TestServer::start(vec![
+            ("503 Service Unavailable", "", "down"),
+            ("503 Service Unavailable", "", "down"),
+        ]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .circuit_breaker(2, Duration::from_secs(60))
+            .build()
+            .unwrap();
+        assert!(client.notify_sync(Notice::builder().build()).is_err());
+        assert_eq!(CircuitState::Closed, client.circuit_state());
+        assert!(client.notify_sync(Notice::builder().build()).is_err());
+        assert_eq!(CircuitState::Open, client.circuit_state());
+
+        match client.notify(Notice::builder().build()) {
+            Err(AirbrakeClientError::CircuitOpen) => (),
+            other => panic!("expected CircuitOpen, got {:?}", other),
+        }
+        assert_eq!(2, server.requests());
+    }
+
+    #[test]
+    fn circuit_is_closed_without_breaker() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .build()
+            .unwrap();
+        assert_eq!(CircuitState::Closed, client.circuit_state());
+    }
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use crate::test_server::TestServer;
+    use crate::{AirbrakeClient, AirbrakeClientError, Notice};
+    use std::time::Duration;
+
+    #[test]
+    fn server_errors_are_retried() {
+        let server = TestServer::start(vec![
+            ("500 Internal Server Error", "", "oops"),
+            ("502 Bad Gateway", "", "oops"),
+            (
+                "201 Created",
+                "",
+                r#"{"id": "1", "url": "https://airbrake.io/1"}"#,
+            ),
+        ]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(1), Duration::from_millis(5))
+            .build()
+            .unwrap();
+        let response = client.notify_sync(Notice::builder().build()).unwrap();
+
+        assert_eq!("1", response.id);
+        assert_eq!(3, server.requests());
+    }
+
+    #[test]
+    fn rejected_notices_are_not_retried() {
+        let server = TestServer::start(vec![("400 Bad Request", "", "{}")]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(1), Duration::from_millis(5))
+            .build()
+            .unwrap();
+        match client.notify_sync(Notice::builder().build()) {
+            Err(AirbrakeClientError::BadRequest { .. }) => (),
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+        assert_eq!(1, server.requests());
+    }
+}
+
+#[cfg(test)]
+mod panic_hook_tests {
+    use crate::panic::HOOK_LOCK;
+    use crate::test_server::TestServer;
+    use crate::AirbrakeClient;
+    use std::panic;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn flush_on_panic_waits_for_the_notice() {
+        let _lock = HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = TestServer::start(vec![(
+            "201 Created",
+            "",
+            r#"{"id": "1", "url": "https://airbrake.io/1"}"#,
+        )]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .flush_on_panic(Duration::from_secs(5))
+            .build()
+            .unwrap();
+
+        let original_hook = panic::take_hook();
+        panic::set_hook(client.panic_hook());
+        let _ = panic::catch_unwind(|| panic!("reported"));
+        panic::set_hook(original_hook);
+
+        assert_eq!(1, server.requests());
+    }
+
+    #[test]
+    fn panic_hook_waits_for_the_notice_by_default() {
+        let _lock = HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = TestServer::start(vec![(
+            "201 Created",
+            "",
+            r#"{"id": "1", "url": "https://airbrake.io/1"}"#,
+        )]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .build()
+            .unwrap();
+
+        let original_hook = panic::take_hook();
+        panic::set_hook(client.panic_hook());
+        let _ = panic::catch_unwind(|| panic!("reported"));
+        panic::set_hook(original_hook);
+
+        assert_eq!(1, server.requests());
+    }
+
+    #[test]
+    fn installed_hook_chains_and_restores_previous_hook() {
+        let _lock = HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = TestServer::start(vec![(
+            "201 Created",
+            "",
+            r#"{"id": "1", "url": "https://airbrake.io/1"}"#,
+        )]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .workers(0)
+            .build()
+            .unwrap();
+
+        let original_hook = panic::take_hook();
+        let previous_calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&previous_calls);
+        panic::set_hook(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let guard = client.install_panic_hook();
+        let _ = panic::catch_unwind(|| panic!("reported"));
+        assert_eq!(1, previous_calls.load(Ordering::SeqCst));
+        assert_eq!(1, server.requests());
+
+        drop(guard);
+        let _ = panic::catch_unwind(|| panic!("not reported"));
+        assert_eq!(2, previous_calls.load(Ordering::SeqCst));
+        assert_eq!(1, server.requests());
+
+        panic::set_hook(original_hook);
+    }
+}
+
+#[cfg(test)]
+mod flush_tests {
+    use crate::test_server::TestServer;
+    use crate::{AirbrakeClient, AirbrakeClientError, Notice, NoticeError, NoticeFrame};
+    use std::time::Duration;
+
+    const CREATED: (&str, &str, &str) = (
+        "201 Created",
+        "",
+        r#"{"id": "1", "url": "https://airbrake.io/1"}"#,
+    );
+
+    #[test]
+    fn flush_waits_for_queued_notices() {
+        let server = TestServer::start(vec![CREATED, CREATED]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .build()
+            .unwrap();
+        client.notify(Notice::builder().build()).unwrap();
+        client.notify(Notice::builder().build()).unwrap();
+
+        assert!(client.flush(Duration::from_secs(5)));
+        assert_eq!(2, server.requests());
+    }
+
+    #[test]
+    fn worker_survives_panics_while_delivering() {
+        let _lock = crate::panic::HOOK_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let server = TestServer::start(vec![CREATED]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .drop_frames(|frame| match frame.function.as_deref() {
+                Some("my_app::panics") => panic!("predicate panicked"),
+                _ => false,
+            })
+            .build()
+            .unwrap();
+        let notice = |function: &str| {
+            let frame = NoticeFrame {
+                function: Some(function.to_string()),
+                ..NoticeFrame::default()
+            };
+            Notice::builder()
+                .add_notice(NoticeError::builder("panic").backtrace(vec![frame]).build())
+                .build()
+        };
+
+        client.notify(notice("my_app::panics")).unwrap();
+        assert!(client.flush(Duration::from_secs(5)));
+        client.notify(notice("my_app::main")).unwrap();
+        assert!(client.flush(Duration::from_secs(5)));
+        assert_eq!(1, server.requests());
+    }
+
+    #[test]
+    fn zero_queue_size_still_queues_a_notice() {
+        let server = TestServer::start(vec![CREATED]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .queue_size(0)
+            .build()
+            .unwrap();
+        client.notify(Notice::builder().build()).unwrap();
+
+        assert!(client.flush(Duration::from_secs(5)));
+        assert_eq!(1, server.requests());
+    }
+
+    #[test]
+    fn close_rejects_new_notices() {
+        let server = TestServer::start(vec![CREATED]);
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .host(&server.host)
+            .build()
+            .unwrap();
+        client.notify(Notice::builder().build()).unwrap();
+
+        assert!(client.close(Duration::from_secs(5)));
+        assert_eq!(1, server.requests());
+        match client.notify(Notice::builder().build()) {
+            Err(AirbrakeClientError::QueueClosed) => (),
+            other => panic!("expected QueueClosed, got {:?}", other),
+        }
+    }
+}
+
 #[cfg(test)]
 mod builder_tests {
     use super::AirbrakeClient;
     use super::AirbrakeClientBuilderError;
 
+    #[test]
+    fn client_starts_workers_by_default() {
+        let client = AirbrakeClient::builder()
+            .project_id("foo")
+            .project_key("bar")
+            .build()
+            .unwrap();
+        assert!(client.queue.is_some());
+    }
+
+    #[test]
+    fn zero_workers_disables_queue() {
+        let client = AirbrakeClient::builder()
+            .project_id("foo")
+            .project_key("bar")
+            .workers(0)
+            .build()
+            .unwrap();
+        assert!(client.queue.is_none());
+    }
+
     #[test]
     fn endpoint_defaults_to_airbrake_server() {
         let client = AirbrakeClient::builder()
@@ -419,6 +1320,29 @@ mod builder_tests {
         )
     }
 
+    #[test]
+    fn client_build_fails_on_invalid_proxy() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .proxy("gopher://127.0.0.1:8080")
+            .build();
+        match client {
+            Err(AirbrakeClientBuilderError::InvalidProxy(_)) => (),
+            other => panic!("expected InvalidProxy, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn client_builds_with_authenticated_proxy() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .proxy("127.0.0.1:8080")
+            .proxy_auth("user", "secret")
+            .no_proxy("localhost,.internal")
+            .build();
+        assert!(client.is_ok());
+    }
+
     #[test]
     fn default_builder_fails_build() {
         let client = AirbrakeClient::builder().build();
@@ -1,4 +1,4 @@
-use super::{Notice, NoticeBuilder};
+use super::{Notice, NoticeBuilder, Severity};
 
 #[derive(Debug, Clone, Default)]
 pub struct ContextBuilder {
@@ -6,7 +6,7 @@ pub struct ContextBuilder {
     _hostname: Option<String>,
     _language: Option<String>,
     _environment: Option<String>,
-    _severity: Option<String>,
+    _severity: Option<Severity>,
     _component: Option<String>,
     _action: Option<String>,
     _user_agent: Option<String>,
@@ -18,6 +18,9 @@ pub struct ContextBuilder {
     _user: Option<ContextUser>,
     _route: Option<String>,
     _http_method: Option<String>,
+    _panic_location: Option<String>,
+    _thread_name: Option<String>,
+    _thread_id: Option<String>,
 }
 
 /// The methods for modifying the ContextBuilder properties are all delegated
@@ -48,8 +51,40 @@ impl ContextBuilder {
             user: self._user.clone(),
             route: self._route.clone(),
             http_method: self._http_method.clone(),
+            panic_location: self._panic_location.clone(),
+            thread_name: self._thread_name.clone(),
+            thread_id: self._thread_id.clone(),
         }
     }
+
+    /// Overwrites the properties that are set on `overrides`
+    pub(crate) fn merge(&mut self, overrides: &ContextBuilder) {
+        merge_field(&mut self._operating_system, &overrides._operating_system);
+        merge_field(&mut self._hostname, &overrides._hostname);
+        merge_field(&mut self._language, &overrides._language);
+        merge_field(&mut self._environment, &overrides._environment);
+        merge_field(&mut self._severity, &overrides._severity);
+        merge_field(&mut self._component, &overrides._component);
+        merge_field(&mut self._action, &overrides._action);
+        merge_field(&mut self._user_agent, &overrides._user_agent);
+        merge_field(&mut self._user_addr, &overrides._user_addr);
+        merge_field(&mut self._remote_addr, &overrides._remote_addr);
+        merge_field(&mut self._version, &overrides._version);
+        merge_field(&mut self._url, &overrides._url);
+        merge_field(&mut self._root_directory, &overrides._root_directory);
+        merge_field(&mut self._user, &overrides._user);
+        merge_field(&mut self._route, &overrides._route);
+        merge_field(&mut self._http_method, &overrides._http_method);
+        merge_field(&mut self._panic_location, &overrides._panic_location);
+        merge_field(&mut self._thread_name, &overrides._thread_name);
+        merge_field(&mut self._thread_id, &overrides._thread_id);
+    }
+}
+
+fn merge_field<T: Clone>(field: &mut Option<T>, value: &Option<T>) {
+    if value.is_some() {
+        *field = value.clone();
+    }
 }
 
 impl From<&Context> for ContextBuilder {
@@ -71,6 +106,9 @@ impl From<&Context> for ContextBuilder {
             _user: context.user.clone(),
             _route: context.route.clone(),
             _http_method: context.http_method.clone(),
+            _panic_location: context.panic_location.clone(),
+            _thread_name: context.thread_name.clone(),
+            _thread_id: context.thread_id.clone(),
         }
     }
 }
@@ -105,7 +143,7 @@ pub struct Context {
     pub environment: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub severity: Option<String>,
+    pub severity: Option<Severity>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub component: Option<String>,
@@ -144,6 +182,19 @@ pub struct Context {
     #[serde(rename = "httpMethod")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub http_method: Option<String>,
+
+    /// The `file:line:column` a panic was raised at
+    #[serde(rename = "panicLocation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panic_location: Option<String>,
+
+    #[serde(rename = "threadName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_name: Option<String>,
+
+    #[serde(rename = "threadId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
 }
 
 impl Context {
@@ -151,7 +202,7 @@ impl Context {
         ContextBuilder::new()
     }
 
-    pub fn new_notice_builder(&self) -> NoticeBuilder {
+    pub fn new_notice_builder(&self) -> NoticeBuilder<'_> {
         let mut notice = Notice::builder();
         notice.context(&self.into());
         notice
@@ -167,9 +218,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._operating_system = Some(os.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -179,9 +230,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._hostname = Some(hostname.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -191,9 +242,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._language = Some(language.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -203,21 +254,27 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._environment = Some(environment.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
     }
 
-    fn severity(&mut self, severity: &str) -> &mut Self {
+    /// ```
+    /// use airbrake::{Context, ContextProperties, Severity};
+    ///
+    /// let context = Context::builder().severity(Severity::Critical).build();
+    /// let context = Context::builder().severity(log::Level::Warn).build();
+    /// ```
+    fn severity<S: Into<Severity>>(&mut self, severity: S) -> &mut Self {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
-                c._severity = Some(severity.to_string());
-                Some(c)
+            .map(|mut c| {
+                c._severity = Some(severity.into());
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -227,9 +284,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._component = Some(component.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -239,9 +296,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._action = Some(action.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -251,9 +308,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._user_agent = Some(user_agent.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -263,9 +320,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._user_addr = Some(user_addr.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -275,9 +332,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._remote_addr = Some(remote_addr.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -287,9 +344,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._version = Some(version.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -299,9 +356,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._url = Some(url.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -311,9 +368,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._root_directory = Some(root_directory.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -323,9 +380,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._user = Some(user);
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -335,9 +392,9 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._route = Some(route.to_string());
-                Some(c)
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -347,9 +404,45 @@ pub trait ContextProperties {
         let updated_context = self
             .get_context()
             .or_else(|| Some(Context::builder()))
-            .and_then(|mut c| {
+            .map(|mut c| {
                 c._http_method = Some(http_method.to_string());
-                Some(c)
+                c
+            })
+            .unwrap();
+        self.set_context(&updated_context)
+    }
+
+    fn panic_location(&mut self, panic_location: &str) -> &mut Self {
+        let updated_context = self
+            .get_context()
+            .or_else(|| Some(Context::builder()))
+            .map(|mut c| {
+                c._panic_location = Some(panic_location.to_string());
+                c
+            })
+            .unwrap();
+        self.set_context(&updated_context)
+    }
+
+    fn thread_name(&mut self, thread_name: &str) -> &mut Self {
+        let updated_context = self
+            .get_context()
+            .or_else(|| Some(Context::builder()))
+            .map(|mut c| {
+                c._thread_name = Some(thread_name.to_string());
+                c
+            })
+            .unwrap();
+        self.set_context(&updated_context)
+    }
+
+    fn thread_id(&mut self, thread_id: &str) -> &mut Self {
+        let updated_context = self
+            .get_context()
+            .or_else(|| Some(Context::builder()))
+            .map(|mut c| {
+                c._thread_id = Some(thread_id.to_string());
+                c
             })
             .unwrap();
         self.set_context(&updated_context)
@@ -485,7 +578,7 @@ mod context_user_tests {
 
 #[cfg(test)]
 mod context_tests {
-    use super::{Context, ContextProperties, ContextUser};
+    use super::{Context, ContextProperties, ContextUser, Severity};
     use serde_json::{self, Value};
     use std::str::FromStr;
 
@@ -585,7 +678,7 @@ mod context_tests {
 
     #[test]
     fn context_severity() {
-        let context = Context::builder().severity("critical").build();
+        let context = Context::builder().severity(Severity::Critical).build();
         let expected_json = r#"
         {
             "notifier": {
@@ -815,4 +908,29 @@ mod context_tests {
             serde_json::json!(context)
         );
     }
+
+    #[test]
+    fn context_panic_location_and_thread() {
+        let context = Context::builder()
+            .panic_location("src/main.rs:3:5")
+            .thread_name("main")
+            .thread_id("ThreadId(1)")
+            .build();
+        let expected_json = r#"
+        {
+            "notifier": {
+                "name": "airbrake-rust",
+                "version": "0.2.0",
+                "url": "https://github.com/airbrake/airbrake-rust"
+            },
+            "panicLocation": "src/main.rs:3:5",
+            "threadName": "main",
+            "threadId": "ThreadId(1)"
+        }
+        "#;
+        assert_eq!(
+            Value::from_str(expected_json).unwrap(),
+            serde_json::json!(context)
+        );
+    }
 }
@@ -0,0 +1,87 @@
+use crate::backtrace::SymbolName;
+
+/// Demangles a symbol name without the hash suffix rustc appends to it,
+/// which changes with every build
+pub(crate) fn demangled_name(name: &SymbolName<'_>) -> String {
+    // The alternate format leaves the hash out of demangled names
+    strip_hash(&format!("{:#}", name)).to_string()
+}
+
+/// Strips a trailing `::h0123456789abcdef` hash from a symbol name
+pub(crate) fn strip_hash(name: &str) -> &str {
+    const HASH_LENGTH: usize = 16;
+    let suffix_length = "::h".len() + HASH_LENGTH;
+    if name.len() <= suffix_length || !name.is_char_boundary(name.len() - suffix_length) {
+        return name;
+    }
+    let (path, suffix) = name.split_at(name.len() - suffix_length);
+    match suffix.strip_prefix("::h") {
+        Some(hash) if hash.chars().all(|c| c.is_ascii_hexdigit()) => path,
+        _ => name,
+    }
+}
+
+/// Drops the generic arguments from a function name, so
+/// `<alloc::boxed::Box<F,A> as core::ops::function::FnOnce<Args>>::call_once`
+/// becomes `<alloc::boxed::Box as core::ops::function::FnOnce>::call_once`
+pub(crate) fn simplify_generics(name: &str) -> String {
+    let mut simplified = String::with_capacity(name.len());
+    let mut depth = 0;
+    let mut previous = None;
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if depth > 0 {
+            match c {
+                // The arrow of `Fn() -> T` doesn't close anything
+                '-' if chars.peek() == Some(&'>') => {
+                    chars.next();
+                }
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => (),
+            }
+            continue;
+        }
+        let follows_identifier = previous.is_some_and(|p: char| p.is_alphanumeric() || p == '_');
+        if c == '<' && follows_identifier {
+            depth = 1;
+            continue;
+        }
+        simplified.push(c);
+        previous = Some(c);
+    }
+    simplified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{simplify_generics, strip_hash};
+
+    #[test]
+    fn hash_suffixes_are_stripped() {
+        assert_eq!(
+            "my_app::main",
+            strip_hash("my_app::main::h1a2b3c4d5e6f7a8b")
+        );
+        assert_eq!("my_app::main", strip_hash("my_app::main"));
+        assert_eq!("my_app::hello_world_h", strip_hash("my_app::hello_world_h"));
+    }
+
+    #[test]
+    fn generics_are_simplified() {
+        assert_eq!(
+            "<alloc::boxed::Box as core::ops::function::FnOnce>::call_once",
+            simplify_generics(
+                "<alloc::boxed::Box<F,A> as core::ops::function::FnOnce<Args>>::call_once"
+            )
+        );
+        assert_eq!(
+            "core::result::Result::map_err",
+            simplify_generics("core::result::Result<T,E>::map_err")
+        );
+        assert_eq!(
+            "my_app::run",
+            simplify_generics("my_app::run<fn() -> Result<(), Error>>")
+        );
+    }
+}
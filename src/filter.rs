@@ -0,0 +1,85 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{AirbrakeClientError, Notice};
+
+/// The outcome of running a filter over a notice
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterDecision {
+    /// Pass the (possibly modified) notice on to the next filter
+    Keep,
+    /// Discard the notice, reporting the reason back to the caller
+    Drop(String),
+}
+
+type Filter = dyn Fn(&mut Notice<'_>) -> FilterDecision + Send + Sync;
+
+/// The filters registered on a client, applied in registration order
+#[derive(Clone, Default)]
+pub(crate) struct NoticeFilters {
+    filters: Vec<Arc<Filter>>,
+}
+
+impl NoticeFilters {
+    pub(crate) fn push<F>(&mut self, filter: F)
+    where
+        F: Fn(&mut Notice<'_>) -> FilterDecision + Send + Sync + 'static,
+    {
+        self.filters.push(Arc::new(filter));
+    }
+
+    /// Runs every filter over the notice, stopping at the first one that
+    /// drops it
+    pub(crate) fn apply(&self, notice: &mut Notice<'_>) -> Result<(), AirbrakeClientError> {
+        for filter in &self.filters {
+            if let FilterDecision::Drop(reason) = filter(notice) {
+                debug!("Airbrake notice dropped by filter: {}", reason);
+                return Err(AirbrakeClientError::NoticeDropped(reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for NoticeFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NoticeFilters({})", self.filters.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilterDecision, NoticeFilters};
+    use crate::{AirbrakeClientError, Notice, NoticeError};
+
+    #[test]
+    fn filters_run_in_registration_order() {
+        let mut filters = NoticeFilters::default();
+        filters.push(|notice: &mut Notice<'_>| {
+            notice.errors.push(NoticeError::new("first", None, None));
+            FilterDecision::Keep
+        });
+        filters.push(|notice: &mut Notice<'_>| {
+            notice.errors.push(NoticeError::new("second", None, None));
+            FilterDecision::Keep
+        });
+
+        let mut notice = Notice::builder().build();
+        assert!(filters.apply(&mut notice).is_ok());
+        let names: Vec<&str> = notice.errors.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(vec!["first", "second"], names);
+    }
+
+    #[test]
+    fn dropping_filter_stops_the_pipeline() {
+        let mut filters = NoticeFilters::default();
+        filters.push(|_: &mut Notice<'_>| FilterDecision::Drop("noisy".to_string()));
+        filters.push(|_: &mut Notice<'_>| panic!("filter should not run"));
+
+        let mut notice = Notice::builder().build();
+        match filters.apply(&mut notice) {
+            Err(AirbrakeClientError::NoticeDropped(reason)) => assert_eq!("noisy", reason),
+            other => panic!("expected NoticeDropped, got {:?}", other),
+        }
+    }
+}
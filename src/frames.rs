@@ -0,0 +1,842 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::backtrace::Backtrace;
+use crate::demangle::simplify_generics;
+use crate::{Notice, NoticeFrame};
+
+const DEFAULT_CODE_RADIUS: usize = 2;
+const DEFAULT_MAX_FRAMES: usize = 128;
+const MAX_CACHED_FILES: usize = 64;
+const MAX_CACHED_ADDRESSES: usize = 4096;
+const MAX_LINE_LENGTH: usize = 200;
+
+/// Path fragments of files that belong to the standard library rather
+/// than the project itself
+const FOREIGN_PATH_FRAGMENTS: &[&str] = &["/rustc/", "/lib/rustlib/"];
+
+/// Functions of the notifier and of the backtrace capture itself
+const NOTIFIER_FRAME_PREFIXES: &[&str] = &["airbrake::", "backtrace::"];
+
+/// Functions of the standard library and of the runtime that starts the
+/// program or its threads. Entries ending in `::` or `_` match as
+/// prefixes, the rest only match the whole function name.
+const STD_FRAME_PREFIXES: &[&str] = &[
+    "std::",
+    "core::",
+    "alloc::",
+    "rust_begin_unwind",
+    "__rust_",
+    "__rustc::",
+    "__libc_start_main",
+    "__libc_start_call_main",
+    "_start",
+    "start_thread",
+    "clone",
+    "clone3",
+    "__scrt_common_main",
+    "__scrt_common_main_seh",
+    "BaseThreadInitThunk",
+    "RtlUserThreadStart",
+];
+
+fn path_matches(path: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|prefix| {
+        if prefix.ends_with("::") || prefix.ends_with('_') {
+            path.starts_with(prefix)
+        } else {
+            path == *prefix
+        }
+    })
+}
+
+/// Splits trait impls, named like `<core::x::Y as core::ops::Fn>::call`,
+/// into the type and the trait
+fn split_trait_impl(function: &str) -> Option<(&str, Option<&str>)> {
+    let inner = function.strip_prefix('<')?;
+    let mut depth = 0;
+    let mut trait_start = None;
+    for (index, c) in inner.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if depth == 0 => {
+                return Some(match trait_start {
+                    Some(start) => (&inner[..start], Some(&inner[start + " as ".len()..index])),
+                    None => (&inner[..index], None),
+                });
+            }
+            '>' => depth -= 1,
+            ' ' if depth == 0 && inner[index..].starts_with(" as ") => trait_start = Some(index),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// A trait impl only matches when both the type and the trait are foreign
+/// to the project, so project traits implemented for std types are kept
+fn function_matches(frame: &NoticeFrame, prefixes: &[&str]) -> bool {
+    frame
+        .function
+        .as_ref()
+        .is_some_and(|function| match split_trait_impl(function) {
+            Some((type_name, trait_name)) => {
+                path_matches(type_name, prefixes)
+                    && trait_name.map_or(true, |trait_name| {
+                        path_matches(trait_name, prefixes)
+                            || path_matches(trait_name, STD_FRAME_PREFIXES)
+                    })
+            }
+            None => path_matches(function, prefixes),
+        })
+}
+
+const PROJECT_ROOT: &str = "/PROJECT_ROOT";
+const CARGO_REGISTRY: &str = "/CARGO_REGISTRY";
+const CARGO_GIT: &str = "/CARGO_GIT";
+const RUSTLIB: &str = "/RUSTLIB";
+
+/// Returns the rest of the file after the root directory, as long as the
+/// root ends at a path boundary
+fn strip_root<'f>(file: &'f str, root_directory: Option<&str>) -> Option<&'f str> {
+    let root = root_directory
+        .map(|root| root.replace('\\', "/"))
+        .filter(|root| !root.trim_end_matches('/').is_empty())?;
+    let rest = file.strip_prefix(root.trim_end_matches('/'))?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+/// Cargo names the directories of registry indexes and git repositories
+/// after the source with a 16 digit hash, like `index.crates.io-1949cf8c6b5b557f`
+fn is_hashed_dir(name: &str) -> bool {
+    name.rsplit_once('-')
+        .is_some_and(|(_, hash)| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Where a file within a cargo home, wherever `CARGO_HOME` points, belongs
+enum CargoSource<'f> {
+    /// `<crate>-<version>/...` of a crate unpacked from a registry
+    Registry(&'f str),
+    /// The repository name and the path within a git checkout
+    Git(&'f str, &'f str),
+}
+
+fn cargo_source(file: &str) -> Option<CargoSource<'_>> {
+    // `$CARGO_HOME/registry/src/<index>/<crate>-<version>/...`
+    for (index, _) in file.match_indices("/registry/src/") {
+        let rest = &file[index + "/registry/src/".len()..];
+        if let Some((dir, rest)) = rest.split_once('/') {
+            if is_hashed_dir(dir) {
+                return Some(CargoSource::Registry(rest));
+            }
+        }
+    }
+    // `$CARGO_HOME/git/checkouts/<repository>-<hash>/<revision>/...`
+    for (index, _) in file.match_indices("/git/checkouts/") {
+        let rest = &file[index + "/git/checkouts/".len()..];
+        if let Some((dir, rest)) = rest.split_once('/') {
+            if let (true, Some((_, path))) = (is_hashed_dir(dir), rest.split_once('/')) {
+                let repository = dir.rsplit_once('-').map_or(dir, |(name, _)| name);
+                return Some(CargoSource::Git(repository, path));
+            }
+        }
+    }
+    None
+}
+
+/// Replaces the parts of a path that differ between build machines: the
+/// root directory, the cargo home the dependency was unpacked or checked
+/// out in and the rust sysroot
+pub(crate) fn normalize_path(file: &str, root_directory: Option<&str>) -> String {
+    let file = file.replace('\\', "/");
+    if let Some(rest) = strip_root(&file, root_directory) {
+        return format!("{}{}", PROJECT_ROOT, rest);
+    }
+    match cargo_source(&file) {
+        Some(CargoSource::Registry(rest)) => return format!("{}/{}", CARGO_REGISTRY, rest),
+        Some(CargoSource::Git(repository, rest)) => {
+            return format!("{}/{}/{}", CARGO_GIT, repository, rest)
+        }
+        None => (),
+    }
+    // `/rustc/<commit>/library/...` in the debug info of the standard
+    // library, `<sysroot>/lib/rustlib/src/rust/library/...` when the
+    // rust-src component is installed
+    if let Some(rest) = file.strip_prefix("/rustc/") {
+        if let Some(index) = rest.find("/library/") {
+            return format!("{}{}", RUSTLIB, &rest[index + "/library".len()..]);
+        }
+    }
+    if let Some(index) = file.find("/lib/rustlib/src/rust/library/") {
+        let rest = &file[index + "/lib/rustlib/src/rust/library".len()..];
+        return format!("{}{}", RUSTLIB, rest);
+    }
+    file
+}
+
+type FrameFilter = Arc<dyn Fn(&NoticeFrame) -> bool + Send + Sync>;
+
+/// Whether a frame's file belongs to the project. Files of dependencies
+/// and the standard library are not, and neither are absolute paths
+/// outside of the root directory. Without a root directory the current
+/// directory stands in for it.
+pub(crate) fn is_in_project(file: &str, root_directory: Option<&str>) -> bool {
+    let file = file.replace('\\', "/");
+    if file.is_empty()
+        || cargo_source(&file).is_some()
+        || FOREIGN_PATH_FRAGMENTS
+            .iter()
+            .any(|fragment| file.contains(fragment))
+    {
+        return false;
+    }
+    if Path::new(&file).is_relative() {
+        return true;
+    }
+    match root_directory {
+        Some(_) => strip_root(&file, root_directory).is_some(),
+        None => env::current_dir()
+            .ok()
+            .is_some_and(|dir| strip_root(&file, dir.to_str()).is_some()),
+    }
+}
+
+type SourceLines = Arc<Vec<String>>;
+
+/// Caches the lines of the source files read for code hunks, so the same
+/// files aren't read for every notice. Clones share the same cache.
+#[derive(Debug, Clone, Default)]
+struct SourceCache {
+    files: Arc<Mutex<HashMap<PathBuf, Option<SourceLines>>>>,
+}
+
+impl SourceCache {
+    /// Files are read without holding the lock, so workers don't wait on
+    /// each other's disk reads
+    fn lines(&self, file: &str, root_directory: Option<&str>) -> Option<SourceLines> {
+        // Relative paths are relative to the directory the project was
+        // built in, which is usually the root directory
+        let path = match root_directory {
+            Some(root) if Path::new(file).is_relative() => Path::new(root).join(file),
+            _ => PathBuf::from(file),
+        };
+        if let Some(lines) = self.lock().get(&path) {
+            return lines.clone();
+        }
+        let contents = fs::read_to_string(&path)
+            .or_else(|_| fs::read_to_string(file))
+            .ok();
+        let lines = contents.map(|contents| Arc::new(contents.lines().map(String::from).collect()));
+        let mut files = self.lock();
+        if files.len() >= MAX_CACHED_FILES {
+            files.clear();
+        }
+        files.insert(path, lines.clone());
+        lines
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, Option<SourceLines>>> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Caches the frames resolved for each instruction address, since the
+/// same code paths tend to fail over and over. Clones share the same cache.
+#[derive(Debug, Clone, Default)]
+struct SymbolCache {
+    addresses: Arc<Mutex<HashMap<usize, Vec<NoticeFrame>>>>,
+}
+
+impl SymbolCache {
+    /// The lock is only held to look up and store addresses, so workers
+    /// resolve symbols in parallel
+    fn resolve(&self, backtrace: &Backtrace, max_frames: usize) -> Vec<NoticeFrame> {
+        let mut frames = Vec::new();
+        for frame in backtrace.frames().iter().take(max_frames) {
+            let address = frame.ip() as usize;
+            let cached = self.lock().get(&address).cloned();
+            let resolved = match cached {
+                Some(resolved) => resolved,
+                None => {
+                    let mut frame = frame.clone();
+                    frame.resolve();
+                    let resolved = NoticeFrame::unroll_frame_symbols(&frame);
+                    let mut addresses = self.lock();
+                    if addresses.len() >= MAX_CACHED_ADDRESSES {
+                        addresses.clear();
+                    }
+                    addresses.insert(address, resolved.clone());
+                    resolved
+                }
+            };
+            frames.extend(resolved);
+        }
+        frames
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<usize, Vec<NoticeFrame>>> {
+        self.addresses.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Cleans up and enriches the backtrace frames of a notice before it is
+/// sent
+#[derive(Clone)]
+pub(crate) struct FrameProcessing {
+    pub(crate) code_radius: usize,
+    pub(crate) strip_notifier_frames: bool,
+    pub(crate) strip_std_frames: bool,
+    pub(crate) frame_filters: Vec<FrameFilter>,
+    pub(crate) normalize_paths: bool,
+    pub(crate) simplify_generics: bool,
+    pub(crate) max_frames: usize,
+    source_cache: SourceCache,
+    symbol_cache: SymbolCache,
+}
+
+impl Default for FrameProcessing {
+    fn default() -> FrameProcessing {
+        FrameProcessing {
+            code_radius: DEFAULT_CODE_RADIUS,
+            strip_notifier_frames: true,
+            strip_std_frames: true,
+            frame_filters: Vec::new(),
+            normalize_paths: true,
+            simplify_generics: false,
+            max_frames: DEFAULT_MAX_FRAMES,
+            source_cache: SourceCache::default(),
+            symbol_cache: SymbolCache::default(),
+        }
+    }
+}
+
+impl fmt::Debug for FrameProcessing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameProcessing")
+            .field("code_radius", &self.code_radius)
+            .field("strip_notifier_frames", &self.strip_notifier_frames)
+            .field("strip_std_frames", &self.strip_std_frames)
+            .field("frame_filters", &self.frame_filters.len())
+            .field("normalize_paths", &self.normalize_paths)
+            .field("simplify_generics", &self.simplify_generics)
+            .field("max_frames", &self.max_frames)
+            .finish()
+    }
+}
+
+/// Fills in `in_project` on frames that haven't been marked yet
+fn mark_in_project(frames: &mut [NoticeFrame], root_directory: Option<&str>) {
+    for frame in frames.iter_mut().filter(|frame| frame.in_project.is_none()) {
+        frame.in_project = Some(
+            frame
+                .file
+                .as_ref()
+                .is_some_and(|file| is_in_project(file, root_directory)),
+        );
+    }
+}
+
+impl FrameProcessing {
+    /// Marks the frames that belong to the project, so notice filters can
+    /// tell them apart before the rest of the processing runs
+    pub(crate) fn mark_frames(&self, notice: &mut Notice<'_>) {
+        let root_directory = notice
+            .context
+            .as_ref()
+            .and_then(|context| context.root_directory.clone());
+        for error in notice.errors.iter_mut() {
+            if let Some(frames) = error.backtrace_frames.as_mut() {
+                mark_in_project(frames, root_directory.as_deref());
+            }
+        }
+    }
+
+    pub(crate) fn apply(&self, notice: &mut Notice<'_>) {
+        let root_directory = notice
+            .context
+            .as_ref()
+            .and_then(|context| context.root_directory.clone());
+        for error in notice.errors.iter_mut() {
+            if let Some(backtrace) = error.unresolved_backtrace.take() {
+                error.backtrace_frames =
+                    Some(self.symbol_cache.resolve(&backtrace, self.max_frames));
+            }
+            if let Some(frames) = error.backtrace_frames.as_mut() {
+                mark_in_project(frames, root_directory.as_deref());
+                self.filter_frames(frames);
+                for frame in frames.iter_mut() {
+                    self.add_code(frame, root_directory.as_deref());
+                    if self.simplify_generics {
+                        frame.function = frame.function.as_deref().map(simplify_generics);
+                    }
+                    if self.normalize_paths {
+                        frame.file = frame
+                            .file
+                            .as_ref()
+                            .map(|file| normalize_path(file, root_directory.as_deref()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the frame is dropped by one of the configured filters
+    fn is_dropped(&self, frame: &NoticeFrame) -> bool {
+        (self.strip_notifier_frames && function_matches(frame, NOTIFIER_FRAME_PREFIXES))
+            || (self.strip_std_frames && function_matches(frame, STD_FRAME_PREFIXES))
+            || self.frame_filters.iter().any(|filter| filter(frame))
+    }
+
+    /// Trims the frames of the backtrace capture above the point it was
+    /// captured at, then drops the frames the filters reject. A backtrace
+    /// that would end up empty is left as it is.
+    fn filter_frames(&self, frames: &mut Vec<NoticeFrame>) {
+        let capture_point = if self.strip_notifier_frames {
+            frames
+                .iter()
+                .rposition(|frame| function_matches(frame, &["backtrace::"]))
+                .map_or(0, |index| index + 1)
+        } else {
+            0
+        };
+        let kept: Vec<NoticeFrame> = frames[capture_point..]
+            .iter()
+            .filter(|frame| !self.is_dropped(frame))
+            .cloned()
+            .collect();
+        if !kept.is_empty() {
+            *frames = kept;
+        }
+    }
+
+    /// Fills in the lines around the frame's line when its file is part of
+    /// the project and can be read
+    fn add_code(&self, frame: &mut NoticeFrame, root_directory: Option<&str>) {
+        if self.code_radius == 0 || frame.code.is_some() || frame.in_project != Some(true) {
+            return;
+        }
+        let (file, line) = match (frame.file.as_ref(), frame.line) {
+            (Some(file), Some(line)) if line > 0 => (file, line as usize),
+            _ => return,
+        };
+        let lines = match self.source_cache.lines(file, root_directory) {
+            Some(lines) => lines,
+            None => return,
+        };
+        let first = line.saturating_sub(self.code_radius).max(1);
+        let last = (line + self.code_radius).min(lines.len());
+        if first > last {
+            return;
+        }
+        let code = (first..=last)
+            .map(|number| {
+                let text: String = lines[number - 1].chars().take(MAX_LINE_LENGTH).collect();
+                (number as i32, text)
+            })
+            .collect();
+        frame.code = Some(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_in_project, normalize_path, FrameProcessing, SourceCache};
+    use crate::backtrace::Backtrace;
+    use crate::{ContextProperties, Notice, NoticeError, NoticeFrame};
+    use std::sync::Arc;
+
+    fn frame(file: &str, line: u32) -> NoticeFrame {
+        NoticeFrame {
+            file: Some(file.to_string()),
+            line: Some(line),
+            ..NoticeFrame::default()
+        }
+    }
+
+    fn process(frame: NoticeFrame, processing: &FrameProcessing) -> NoticeFrame {
+        let error = NoticeError::new("foo", None, Some(vec![frame]));
+        let mut notice = Notice::builder().add_notice(error).build();
+        processing.apply(&mut notice);
+        notice.errors[0]
+            .backtrace_frames
+            .as_mut()
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn dependency_and_std_frames_are_not_in_project() {
+        assert!(is_in_project("src/main.rs", None));
+        assert!(is_in_project("/app/src/main.rs", Some("/app")));
+        assert!(!is_in_project("/elsewhere/src/main.rs", Some("/app")));
+        assert!(!is_in_project("/application/src/main.rs", Some("/app")));
+        assert!(is_in_project("/app/src/main.rs", Some("/app/")));
+        assert!(!is_in_project(
+            "/usr/local/cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.0/src/de.rs",
+            None
+        ));
+        assert!(!is_in_project(
+            "/home/ci/.cargo/git/checkouts/serde-a1b2c3d4e5f6a7b8/1a2b3c4/serde/src/de.rs",
+            None
+        ));
+        assert!(is_in_project("/app/registry/src/main.rs", Some("/app")));
+        assert!(!is_in_project(
+            "/home/ci/.cargo/registry/src/github.com-1ecc6299db9ec823/serde-1.0.0/src/de.rs",
+            None
+        ));
+        assert!(!is_in_project(
+            "/rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/std/src/panicking.rs",
+            None
+        ));
+    }
+
+    #[test]
+    fn absolute_paths_without_root_are_in_project_under_current_dir() {
+        let current_dir = std::env::current_dir().unwrap();
+        let main = current_dir.join("src/main.rs");
+        assert!(is_in_project(main.to_str().unwrap(), None));
+        assert!(!is_in_project("/build/glibc/csu/libc-start.c", None));
+        assert!(!is_in_project("", None));
+        assert!(!is_in_project("", Some("/app")));
+    }
+
+    fn function_frame(function: &str, file: &str) -> NoticeFrame {
+        NoticeFrame {
+            function: Some(function.to_string()),
+            file: Some(file.to_string()),
+            ..NoticeFrame::default()
+        }
+    }
+
+    fn functions(frames: Vec<NoticeFrame>, processing: &FrameProcessing) -> Vec<String> {
+        let error = NoticeError::new("foo", None, Some(frames));
+        let mut notice = Notice::builder().add_notice(error).build();
+        processing.apply(&mut notice);
+        notice.errors[0]
+            .backtrace_frames
+            .take()
+            .unwrap()
+            .into_iter()
+            .filter_map(|frame| frame.function)
+            .collect()
+    }
+
+    fn captured_frames() -> Vec<NoticeFrame> {
+        vec![
+            function_frame(
+                "backtrace::backtrace::trace",
+                "/home/ci/.cargo/registry/src/index.crates.io-6f17d22bba15001f/backtrace-0.3.0/src/lib.rs",
+            ),
+            function_frame(
+                "backtrace::capture::Backtrace::new",
+                "/home/ci/.cargo/registry/src/index.crates.io-6f17d22bba15001f/backtrace-0.3.0/src/capture.rs",
+            ),
+            function_frame("my_app::load_config", "src/config.rs"),
+            function_frame(
+                "core::result::Result<T,E>::map_err",
+                "/rustc/abc/library/core/src/result.rs",
+            ),
+            function_frame(
+                "<alloc::boxed::Box<F> as core::ops::function::FnOnce<A>>::call_once",
+                "/rustc/abc/library/alloc/src/boxed.rs",
+            ),
+            function_frame(
+                "airbrake::client::AirbrakeClient::notify",
+                "/home/ci/.cargo/registry/src/index.crates.io-6f17d22bba15001f/airbrake-0.2.0/src/client.rs",
+            ),
+            function_frame("my_app::main", "src/main.rs"),
+            function_frame(
+                "std::rt::lang_start_internal",
+                "/rustc/abc/library/std/src/rt.rs",
+            ),
+            function_frame("__libc_start_main", "/build/glibc/csu/libc-start.c"),
+            function_frame("_start", ""),
+        ]
+    }
+
+    #[test]
+    fn notifier_std_and_runtime_frames_are_stripped() {
+        assert_eq!(
+            vec!["my_app::load_config", "my_app::main"],
+            functions(captured_frames(), &FrameProcessing::default())
+        );
+    }
+
+    #[test]
+    fn runtime_symbols_are_matched_exactly() {
+        let frames = vec![
+            function_frame("_start_server", "src/server.rs"),
+            function_frame("clone_config", "src/config.rs"),
+            function_frame(
+                "__rust_begin_short_backtrace",
+                "/rustc/abc/library/std/src/rt.rs",
+            ),
+            function_frame("_start", ""),
+            function_frame("clone", ""),
+        ];
+        assert_eq!(
+            vec!["_start_server", "clone_config"],
+            functions(frames, &FrameProcessing::default())
+        );
+    }
+
+    #[test]
+    fn project_traits_on_std_types_are_kept() {
+        let frames = vec![
+            function_frame("<std::fs::File as my_app::Loader>::load", "src/loader.rs"),
+            function_frame(
+                "<std::fs::File as std::io::Read>::read",
+                "/rustc/abc/library/std/src/fs.rs",
+            ),
+            function_frame(
+                "<airbrake::Notice as core::fmt::Debug>::fmt",
+                "src/notice.rs",
+            ),
+            function_frame("my_app::main", "src/main.rs"),
+        ];
+        assert_eq!(
+            vec!["<std::fs::File as my_app::Loader>::load", "my_app::main"],
+            functions(frames, &FrameProcessing::default())
+        );
+    }
+
+    #[test]
+    fn drop_frames_can_use_in_project() {
+        let frames = vec![
+            function_frame(
+                "serde::de::Deserialize::deserialize",
+                "/home/ci/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.0/src/de.rs",
+            ),
+            function_frame("my_app::main", "src/main.rs"),
+        ];
+        let processing = FrameProcessing {
+            frame_filters: vec![Arc::new(|frame| frame.in_project == Some(false))],
+            ..FrameProcessing::default()
+        };
+        assert_eq!(vec!["my_app::main"], functions(frames, &processing));
+    }
+
+    #[test]
+    fn frame_stripping_can_be_disabled() {
+        let processing = FrameProcessing {
+            strip_notifier_frames: false,
+            strip_std_frames: false,
+            ..FrameProcessing::default()
+        };
+        assert_eq!(10, functions(captured_frames(), &processing).len());
+    }
+
+    #[test]
+    fn custom_frame_filters_drop_frames() {
+        let processing = FrameProcessing {
+            frame_filters: vec![Arc::new(|frame| {
+                frame.function.as_deref() == Some("my_app::load_config")
+            })],
+            ..FrameProcessing::default()
+        };
+        assert_eq!(
+            vec!["my_app::main"],
+            functions(captured_frames(), &processing)
+        );
+    }
+
+    #[test]
+    fn frames_are_marked_in_project() {
+        let error = NoticeError::new("foo", None, Some(captured_frames()));
+        let mut notice = Notice::builder().add_notice(error).build();
+        FrameProcessing {
+            strip_std_frames: false,
+            ..FrameProcessing::default()
+        }
+        .apply(&mut notice);
+        let in_project: Vec<Option<bool>> = notice.errors[0]
+            .backtrace_frames
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|frame| frame.in_project)
+            .collect();
+        assert_eq!(
+            vec![
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false)
+            ],
+            in_project
+        );
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        assert_eq!(
+            "/PROJECT_ROOT/src/main.rs",
+            normalize_path("/home/ci/app/src/main.rs", Some("/home/ci/app/"))
+        );
+        assert_eq!(
+            "/home/ci/application/src/main.rs",
+            normalize_path("/home/ci/application/src/main.rs", Some("/home/ci/app"))
+        );
+        assert_eq!(
+            "/CARGO_REGISTRY/serde-1.0.0/src/de.rs",
+            normalize_path(
+                "/home/ci/.cargo/registry/src/github.com-1ecc6299db9ec823/serde-1.0.0/src/de.rs",
+                None
+            )
+        );
+        assert_eq!(
+            "/CARGO_REGISTRY/serde-1.0.0/src/de.rs",
+            normalize_path(
+                "/usr/local/cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.0/src/de.rs",
+                None
+            )
+        );
+        assert_eq!(
+            "/CARGO_GIT/serde/serde/src/de.rs",
+            normalize_path(
+                "/home/ci/.cargo/git/checkouts/serde-a1b2c3d4e5f6a7b8/1a2b3c4/serde/src/de.rs",
+                None
+            )
+        );
+        assert_eq!(
+            "/app/registry/src/main.rs",
+            normalize_path("/app/registry/src/main.rs", None)
+        );
+        assert_eq!(
+            "/RUSTLIB/std/src/panicking.rs",
+            normalize_path(
+                "/rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/std/src/panicking.rs",
+                None
+            )
+        );
+        assert_eq!(
+            "/RUSTLIB/core/src/result.rs",
+            normalize_path(
+                "/home/ci/.rustup/toolchains/stable/lib/rustlib/src/rust/library/core/src/result.rs",
+                None
+            )
+        );
+        assert_eq!("src/main.rs", normalize_path("src/main.rs", Some("/app")));
+    }
+
+    #[test]
+    fn frame_files_are_normalized_after_reading_code() {
+        let root = std::env::current_dir().unwrap();
+        let root = root.to_str().unwrap();
+        let file = format!("{}/src/frames.rs", root);
+        let error = NoticeError::new("foo", None, Some(vec![frame(&file, 2)]));
+        let mut notice = Notice::builder()
+            .root_directory(root)
+            .add_notice(error)
+            .build();
+        FrameProcessing::default().apply(&mut notice);
+        let frame = &notice.errors[0].backtrace_frames.as_ref().unwrap()[0];
+
+        assert_eq!(Some("/PROJECT_ROOT/src/frames.rs"), frame.file.as_deref());
+        assert!(frame.code.is_some());
+    }
+
+    fn resolve(error: &NoticeError, processing: &FrameProcessing) -> Vec<NoticeFrame> {
+        let mut notice = Notice::builder().add_notice(error.clone()).build();
+        processing.apply(&mut notice);
+        assert!(notice.errors[0].unresolved_backtrace.is_none());
+        notice.errors[0].backtrace_frames.take().unwrap()
+    }
+
+    /// The tests themselves are notifier frames, so they are kept here
+    fn unfiltered() -> FrameProcessing {
+        FrameProcessing {
+            strip_notifier_frames: false,
+            strip_std_frames: false,
+            ..FrameProcessing::default()
+        }
+    }
+
+    #[test]
+    fn unresolved_backtraces_are_resolved_when_processed() {
+        let backtrace = Backtrace::new_unresolved();
+        let error = NoticeError::builder("foo")
+            .raw_backtrace(&backtrace)
+            .build();
+        assert!(error.backtrace_frames.is_none());
+
+        let frames = resolve(&error, &unfiltered());
+        assert!(frames.iter().any(|frame| frame
+            .function
+            .as_deref()
+            .is_some_and(|f| f.contains("unresolved_backtraces_are_resolved_when_processed"))));
+
+        // The second time around the frames come from the cache
+        assert_eq!(frames.len(), resolve(&error, &unfiltered()).len());
+    }
+
+    #[test]
+    fn unresolved_backtraces_are_capped() {
+        let backtrace = Backtrace::new_unresolved();
+        let error = NoticeError::builder("foo")
+            .raw_backtrace(&backtrace)
+            .build();
+        let capped = FrameProcessing {
+            max_frames: 2,
+            ..unfiltered()
+        };
+
+        let frames = resolve(&error, &capped);
+        assert!(!frames.is_empty());
+        assert!(frames.len() < resolve(&error, &unfiltered()).len());
+    }
+
+    #[test]
+    fn code_is_read_around_the_frame_line() {
+        let frame = process(frame("src/frames.rs", 2), &FrameProcessing::default());
+        let code = frame.code.unwrap();
+
+        assert_eq!(4, code.len());
+        assert_eq!("use std::collections::HashMap;", code[&1]);
+        assert_eq!("use std::fmt;", code[&3]);
+    }
+
+    #[test]
+    fn source_cache_is_keyed_by_root_directory() {
+        let base = std::env::temp_dir().join(format!("airbrake-roots-{}", std::process::id()));
+        let roots: Vec<String> = ["first", "second"]
+            .iter()
+            .map(|name| {
+                let root = base.join(name);
+                std::fs::create_dir_all(root.join("src")).unwrap();
+                std::fs::write(root.join("src/lib.rs"), format!("// {}\n", name)).unwrap();
+                root.to_str().unwrap().to_string()
+            })
+            .collect();
+
+        let cache = SourceCache::default();
+        let first = cache.lines("src/lib.rs", Some(&roots[0])).unwrap();
+        let second = cache.lines("src/lib.rs", Some(&roots[1])).unwrap();
+        std::fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!("// first", first[0]);
+        assert_eq!("// second", second[0]);
+    }
+
+    #[test]
+    fn code_is_skipped_when_disabled_or_unreadable() {
+        let disabled = FrameProcessing {
+            code_radius: 0,
+            ..FrameProcessing::default()
+        };
+        assert!(process(frame("src/frames.rs", 2), &disabled).code.is_none());
+        let missing = process(frame("src/missing.rs", 2), &FrameProcessing::default());
+        assert!(missing.code.is_none());
+    }
+}
@@ -0,0 +1,122 @@
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::{AirbrakeClient, AirbrakeClientBuilder, AirbrakeClientError, NoticeResponse};
+
+/// The process-wide client installed by `init`
+static CLIENT: RwLock<Option<Arc<AirbrakeClient>>> = RwLock::new(None);
+
+#[cfg(test)]
+pub(crate) static CLIENT_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Configures an Airbrake notifier and installs it as the process-wide
+/// client used by `airbrake::notify`, `airbrake::notify_sync` and
+/// `airbrake::flush`. Calling `init` again replaces the client.
+///
+/// # Examples
+///
+/// ```
+/// airbrake::init(|config| {
+///     config.project_id("113743");
+///     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+/// });
+///
+/// let err = std::io::Error::last_os_error();
+/// let _ = airbrake::notify(err);
+/// ```
+pub fn init<F>(builder_callback: F)
+where
+    F: Fn(&mut AirbrakeClientBuilder),
+{
+    set_client(crate::configure(builder_callback));
+}
+
+/// Installs an already built client as the process-wide client
+pub fn set_client(client: AirbrakeClient) {
+    *CLIENT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(client));
+}
+
+/// Calls `f` with the process-wide client, returning `None` when `init`
+/// hasn't been called. Useful for building notices by hand.
+pub fn with_client<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&AirbrakeClient) -> R,
+{
+    // Clone the client out of the lock so sending doesn't block `init`
+    let client = CLIENT.read().unwrap_or_else(|e| e.into_inner()).clone();
+    client.as_deref().map(f)
+}
+
+/// Queues the error for delivery with the process-wide client. Does
+/// nothing and returns `AirbrakeClientError::NoticeClientNotSet` when
+/// `init` hasn't been called.
+pub fn notify<E: Error + 'static>(error: E) -> Result<(), AirbrakeClientError> {
+    with_client(|client| client.notify(client.new_notice_builder().add_error(error).build()))
+        .unwrap_or(Err(AirbrakeClientError::NoticeClientNotSet))
+}
+
+/// Sends the error with the process-wide client, blocking until Airbrake
+/// responds. Does nothing and returns
+/// `AirbrakeClientError::NoticeClientNotSet` when `init` hasn't been
+/// called.
+pub fn notify_sync<E: Error + 'static>(error: E) -> Result<NoticeResponse, AirbrakeClientError> {
+    with_client(|client| client.notify_sync(client.new_notice_builder().add_error(error).build()))
+        .unwrap_or(Err(AirbrakeClientError::NoticeClientNotSet))
+}
+
+/// Flushes the notices queued on the process-wide client. Returns `true`
+/// straight away when `init` hasn't been called.
+pub fn flush(timeout: Duration) -> bool {
+    with_client(|client| client.flush(timeout)).unwrap_or(true)
+}
+
+#[cfg(test)]
+pub(crate) fn reset() {
+    *CLIENT.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reset, CLIENT_LOCK};
+    use crate::test_server::TestServer;
+    use crate::AirbrakeClientError;
+    use std::io;
+    use std::time::Duration;
+
+    const CREATED: (&str, &str, &str) = (
+        "201 Created",
+        "",
+        r#"{"id": "1", "url": "https://airbrake.io/1"}"#,
+    );
+
+    #[test]
+    fn free_functions_do_nothing_before_init() {
+        let _lock = CLIENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        reset();
+
+        let error = io::Error::other("boom");
+        match crate::notify(error) {
+            Err(AirbrakeClientError::NoticeClientNotSet) => (),
+            other => panic!("expected NoticeClientNotSet, got {:?}", other),
+        }
+        assert!(crate::flush(Duration::from_secs(1)));
+        assert!(crate::with_client(|_| ()).is_none());
+    }
+
+    #[test]
+    fn free_functions_use_the_installed_client() {
+        let _lock = CLIENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = TestServer::start(vec![CREATED, CREATED]);
+        crate::init(|config| {
+            config.project("foo", "bar").host(&server.host);
+        });
+
+        let response = crate::notify_sync(io::Error::other("boom")).unwrap();
+        assert_eq!("1", response.id);
+        crate::notify(io::Error::other("boom")).unwrap();
+        assert!(crate::flush(Duration::from_secs(5)));
+        assert_eq!(2, server.requests());
+        reset();
+    }
+}
@@ -84,8 +84,9 @@
 //! ### proxy
 //!
 //! If your server is not able to directly reach Airbrake, you can use proxy
-//! support. By default, Airbrake Rust uses direct connection. Note: proxy
-//! authentication is not supported yet.
+//! support. By default, Airbrake Rust uses direct connection. HTTP and HTTPS
+//! proxies are supported out of the box, socks5 proxies require the `socks`
+//! cargo feature. Hosts listed in `no_proxy` bypass the proxy.
 //!
 //! ```
 //! let mut airbrake = airbrake::configure(|config| {
@@ -94,6 +95,49 @@
 //!     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
 //!     // Setting the proxy
 //!     config.proxy("127.0.0.1:8080");
+//!     config.proxy_auth("username", "password");
+//!     config.no_proxy("localhost,.internal.example.com");
+//! });
+//! ```
+//!
+//! ### workers & queue_size
+//!
+//! `notify` doesn't send notices itself, it places them on a bounded queue
+//! that is drained by a pool of background threads. By default a single
+//! worker drains a queue of up to 100 notices. When the queue is full,
+//! `notify` returns `AirbrakeClientError::QueueFull` rather than blocking.
+//! Setting `workers` to `0` disables the queue entirely. Call
+//! `airbrake.close(timeout)` before the process exits so queued notices
+//! aren't lost. The panic hook waits up to two seconds for its own notice,
+//! which can be changed with `flush_on_panic`.
+//!
+//! ```
+//! let mut airbrake = airbrake::configure(|config| {
+//!     // Project ID & Key are required
+//!     config.project_id("113743");
+//!     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+//!     // Deliver notices with four threads
+//!     config.workers(4);
+//!     config.queue_size(500);
+//! });
+//! ```
+//!
+//! ### timeout, connect_timeout & max_retries
+//!
+//! Requests to Airbrake use reqwest's default timeouts and aren't retried
+//! by default. With `max_retries` set, connection errors, timeouts and 5xx
+//! responses are retried with an exponential backoff, which can be tuned
+//! with `retry_backoff`.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! let mut airbrake = airbrake::configure(|config| {
+//!     config.project_id("113743");
+//!     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+//!     config.timeout(Duration::from_secs(10));
+//!     config.connect_timeout(Duration::from_secs(2));
+//!     config.max_retries(3);
 //! });
 //! ```
 //!
@@ -139,12 +183,13 @@
 //! As the second parameter, accepts a hash with additional data. That data will be
 //! displayed in the _Params_ tab in your project's dashboard.
 //!
-//! #### airbrake.notify
+//! #### airbrake.notify_sync
 //!
-//! Sends an error to Airbrake *synchronously*. `error` must implement the
-//! [`std::error::Error`][stderror] trait. Returns
-//! [`rustc_serialize::json::Json`][json-object]. Accepts the same
-//! parameters as [`Airbrake.notify`](#airbrakenotify).
+//! Sends an error to Airbrake *synchronously*, blocking the calling thread
+//! until the request completes. Accepts the same parameters as
+//! [`airbrake.notify`](#airbrakenotify). Returns a `NoticeResponse` with the
+//! id and dashboard url of the created notice, or an `AirbrakeClientError`
+//! describing why Airbrake rejected it.
 //!
 //! ```
 //! let mut airbrake = airbrake::configure(|config| {
@@ -156,7 +201,65 @@
 //! let notice = airbrake::Notice::builder()
 //!     .add_error(err)
 //!     .build();
-//! airbrake.notify(notice);
+//! airbrake.notify_sync(notice);
+//! ```
+//!
+//! ## Scoped context
+//!
+//! Request-specific context like the user, route and HTTP method can be set
+//! once per request with `airbrake::with_scope`, and is merged into the
+//! context of every notice built with `new_notice_builder` within it. With
+//! the `async` feature, `airbrake::with_scope_async` scopes a future through
+//! a tokio task-local instead of a thread-local.
+//!
+//! ```
+//! use airbrake::ContextProperties;
+//!
+//! let airbrake = airbrake::configure(|config| {
+//!     config.project_id("113743");
+//!     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+//! });
+//!
+//! airbrake::with_scope(
+//!     |scope| {
+//!         scope.route("/checkout").http_method("POST");
+//!     },
+//!     || {
+//!         let err = std::io::Error::last_os_error();
+//!         let _ = airbrake.new_notice_builder().add_error(err).build().send();
+//!     },
+//! );
+//! ```
+//!
+//! ## Global client
+//!
+//! `airbrake::init` takes the same configuration as `airbrake::configure`,
+//! but installs the client for the whole process so that code without a
+//! handle to it can still report errors with `airbrake::notify`,
+//! `airbrake::notify_sync` and `airbrake::flush`. Before `init` is called
+//! these do nothing.
+//!
+//! ```
+//! airbrake::init(|config| {
+//!     config.project_id("113743");
+//!     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+//! });
+//!
+//! // ... deep inside a library
+//! let err = std::io::Error::last_os_error();
+//! let _ = airbrake::notify(err);
+//! ```
+//!
+//! ## Async services
+//!
+//! Enabling the `async` cargo feature adds `AsyncAirbrakeClient`, which is
+//! configured through the same builder but sends notices with non-blocking
+//! I/O, so it can be awaited from tokio or actix handlers without stalling
+//! the executor.
+//!
+//! ```toml
+//! [dependencies]
+//! airbrake = { version = "0.2", features = ["async"] }
 //! ```
 //!
 //! [airbrake.io]: https://airbrake.io
@@ -175,7 +278,14 @@
 //! Parameters.
 //!
 //! Typically you won't need to work with the NoticeError directly, since you
-//! can add errors to a Notice using the `.add_error` function.
+//! can add errors to a Notice using the `.add_error` function. `.add_error`
+//! also adds a NoticeError for every cause in the error's `source()` chain,
+//! which can be limited with `.error_chain_depth`. Causes are only known as
+//! `dyn Error`, so apart from std error types they are named from their
+//! Debug output, which gives a struct's bare name or an enum's variant.
+//! Backtraces captured with `std::backtrace::Backtrace` can be attached
+//! with `.add_error_with_std_backtrace`, and the `anyhow` and `eyre` cargo
+//! features add `.add_anyhow_error` and `.add_eyre_report`.
 //!
 //! ```
 //! use std::error::Error;
@@ -273,14 +383,45 @@ extern crate log;
 #[macro_use]
 extern crate more_asserts;
 
+#[cfg(feature = "async")]
+mod async_client;
+mod circuit_breaker;
 mod client;
 mod context;
+mod demangle;
+mod filter;
+mod frames;
+mod global;
 mod notice;
+mod panic;
+mod pipeline;
+mod proxy;
+mod queue;
+mod rate_limit;
+mod redaction;
+mod response;
+mod retry;
+mod scope;
+mod severity;
+#[cfg(test)]
+mod test_server;
 
+#[cfg(feature = "async")]
+pub use async_client::AsyncAirbrakeClient;
 pub use backtrace;
+pub use circuit_breaker::CircuitState;
 pub use client::{AirbrakeClient, AirbrakeClientBuilder, AirbrakeClientError};
 pub use context::{Context, ContextBuilder, ContextProperties, ContextUser, CONTEXT_NOTIFIER};
+pub use filter::FilterDecision;
+pub use global::{flush, init, notify, notify_sync, set_client, with_client};
 pub use notice::*;
+pub use panic::PanicHookGuard;
+pub use redaction::KeyPattern;
+pub use response::NoticeResponse;
+#[cfg(feature = "async")]
+pub use scope::with_scope_async;
+pub use scope::{with_scope, Scope};
+pub use severity::Severity;
 
 /// Configures an Airbrake notifier.
 ///
@@ -1,13 +1,16 @@
+use super::error_name::{debug_name, type_name_of};
 use super::NoticeFrame;
 use crate::backtrace::Backtrace;
+use crate::panic::{panic_message, strip_panic_frames};
 use std::error::Error;
-use std::panic::PanicInfo;
+use std::panic::PanicHookInfo;
 
 #[derive(Default, Clone)]
 pub struct NoticeErrorBuilder {
     pub name: String,
     pub message: Option<String>,
     pub backtrace: Option<Vec<NoticeFrame>>,
+    pub unresolved_backtrace: Option<Backtrace>,
 }
 
 impl NoticeErrorBuilder {
@@ -16,6 +19,7 @@ impl NoticeErrorBuilder {
             name: name.to_string(),
             message: None,
             backtrace: None,
+            unresolved_backtrace: None,
         }
     }
 
@@ -30,13 +34,25 @@ impl NoticeErrorBuilder {
         self
     }
 
+    /// Sets the backtrace from one captured by the backtrace crate. A
+    /// backtrace captured with `Backtrace::new_unresolved()` is resolved
+    /// by the client right before the notice is sent, off the hot path.
     pub fn raw_backtrace(&mut self, backtrace: &Backtrace) -> &mut NoticeErrorBuilder {
-        self.backtrace = Some(NoticeFrame::from_backtrace(backtrace));
+        if NoticeFrame::is_unresolved(backtrace) {
+            self.backtrace = None;
+            self.unresolved_backtrace = Some(backtrace.clone());
+        } else {
+            self.backtrace = Some(NoticeFrame::from_backtrace(backtrace));
+            self.unresolved_backtrace = None;
+        }
         self
     }
 
     pub fn build(&self) -> NoticeError {
-        NoticeError::new(&self.name, self.message.clone(), self.backtrace.clone())
+        let mut notice_error =
+            NoticeError::new(&self.name, self.message.clone(), self.backtrace.clone());
+        notice_error.unresolved_backtrace = self.unresolved_backtrace.clone();
+        notice_error
     }
 }
 
@@ -50,6 +66,11 @@ pub struct NoticeError {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub backtrace_frames: Option<Vec<NoticeFrame>>,
+
+    /// A backtrace whose symbols are resolved into `backtrace_frames` by
+    /// the client before the notice is sent
+    #[serde(skip)]
+    pub unresolved_backtrace: Option<Backtrace>,
 }
 
 impl NoticeError {
@@ -62,6 +83,7 @@ impl NoticeError {
             name: name.to_string(),
             message,
             backtrace_frames,
+            unresolved_backtrace: None,
         }
     }
 
@@ -69,29 +91,172 @@ impl NoticeError {
         NoticeErrorBuilder::new(name)
     }
 
-    pub fn from_panic_backtrace(panic_info: &PanicInfo, backtrace: &Backtrace) -> NoticeError {
-        // TODO: PanicInfo has a `message()` on nightly which might be easier to work with
-        // here, but for now we'll just deal with `payload()`
-        let opt_message: Option<&str> = panic_info.payload().downcast_ref::<&str>().copied();
+    /// Builds a NoticeError from within a panic hook. The frames of the
+    /// hook itself are left out of the backtrace.
+    pub fn from_panic_backtrace(panic_info: &PanicHookInfo, backtrace: &Backtrace) -> NoticeError {
         let mut builder = NoticeError::builder("panic");
-        builder.raw_backtrace(&backtrace);
-        if let Some(message) = opt_message {
-            builder.message(message);
+        builder.backtrace(strip_panic_frames(NoticeFrame::from_backtrace(backtrace)));
+        if let Some(message) = panic_message(panic_info) {
+            builder.message(&message);
         }
         builder.build()
     }
+
+    /// Builds one NoticeError for the error and one for each of its
+    /// `source()` causes, outermost first. `max_depth` caps the number of
+    /// NoticeErrors returned, but the error itself is always included.
+    /// Since the errors are only known as `dyn Error`, those that aren't
+    /// std error types get a best-effort name from the start of their Debug
+    /// output. That is a struct's name without its module path, but only
+    /// the variant for an enum, e.g. `Io` for `AppError::Io(..)`.
+    pub fn chain(error: &(dyn Error + 'static), max_depth: Option<usize>) -> Vec<NoticeError> {
+        let mut errors = Vec::new();
+        let mut current = Some(error);
+        while let Some(error) = current {
+            if max_depth.is_some_and(|max_depth| errors.len() >= max_depth.max(1)) {
+                break;
+            }
+            errors.push(NoticeError::from_dyn(error));
+            current = error.source();
+        }
+        errors
+    }
+
+    fn from_dyn(error: &(dyn Error + 'static)) -> NoticeError {
+        NoticeError::new(&debug_name(error), Some(error.to_string()), None)
+    }
 }
 
-impl<'a, E: Error> From<E> for NoticeError {
+impl<E: Error> From<E> for NoticeError {
     fn from(error: E) -> NoticeError {
-        let name = format!("{:?}", error)
-            .split_whitespace()
-            .next()
-            .unwrap()
-            .to_string();
-        let message = Some(format!("{}", error));
-        let backtrace = None;
-
-        NoticeError::new(&name, message, backtrace)
+        NoticeError::new(&type_name_of::<E>(false), Some(error.to_string()), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoticeError;
+    use std::error::Error;
+    use std::fmt;
+    use std::io;
+
+    #[derive(Debug)]
+    struct ConfigError {
+        source: io::Error,
+    }
+
+    impl fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "could not read config")
+        }
+    }
+
+    impl Error for ConfigError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.source)
+        }
+    }
+
+    #[derive(Debug)]
+    struct StartupError {
+        source: ConfigError,
+    }
+
+    impl fmt::Display for StartupError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "failed to start")
+        }
+    }
+
+    impl Error for StartupError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.source)
+        }
+    }
+
+    fn startup_error() -> StartupError {
+        StartupError {
+            source: ConfigError {
+                source: io::Error::new(io::ErrorKind::NotFound, "config.toml"),
+            },
+        }
+    }
+
+    #[test]
+    fn chain_includes_every_source_in_order() {
+        let errors = NoticeError::chain(&startup_error(), None);
+        let names: Vec<&str> = errors.iter().map(|e| e.name.as_str()).collect();
+        let messages: Vec<&str> = errors
+            .iter()
+            .map(|e| e.message.as_deref().unwrap())
+            .collect();
+
+        assert_eq!(vec!["StartupError", "ConfigError", "std::io::Error"], names);
+        assert_eq!(
+            vec!["failed to start", "could not read config", "config.toml"],
+            messages
+        );
+    }
+
+    #[derive(Debug)]
+    enum AppError {
+        Io(io::Error),
+    }
+
+    impl fmt::Display for AppError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "i/o failed")
+        }
+    }
+
+    impl Error for AppError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                AppError::Io(source) => Some(source),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Outer {
+        source: AppError,
+    }
+
+    impl fmt::Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "request failed")
+        }
+    }
+
+    impl Error for Outer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.source)
+        }
+    }
+
+    #[test]
+    fn enum_causes_are_named_after_their_variant() {
+        let error = Outer {
+            source: AppError::Io(io::Error::new(io::ErrorKind::NotFound, "config.toml")),
+        };
+        let names: Vec<String> = NoticeError::chain(&error, None)
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(vec!["Outer", "Io", "std::io::Error"], names);
+    }
+
+    #[test]
+    fn chain_respects_max_depth() {
+        let errors = NoticeError::chain(&startup_error(), Some(2));
+        assert_eq!(2, errors.len());
+        assert_eq!("ConfigError", errors[1].name);
+    }
+
+    #[test]
+    fn chain_always_includes_the_error() {
+        let errors = NoticeError::chain(&startup_error(), Some(0));
+        assert_eq!(1, errors.len());
+        assert_eq!("StartupError", errors[0].name);
     }
 }
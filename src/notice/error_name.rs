@@ -0,0 +1,221 @@
+use std::error::Error;
+
+/// Overrides the `type` an error is reported to Airbrake with. Errors that
+/// implement it can be added with `NoticeBuilder::add_named_error`.
+///
+/// ```
+/// use airbrake::{AirbrakeErrorName, Notice};
+/// use std::fmt;
+///
+/// #[derive(Debug)]
+/// enum DbError {
+///     Timeout,
+/// }
+///
+/// impl std::error::Error for DbError {}
+/// impl fmt::Display for DbError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "query timed out")
+///     }
+/// }
+///
+/// impl AirbrakeErrorName for DbError {
+///     fn airbrake_error_name(&self) -> String {
+///         match self {
+///             DbError::Timeout => "DbError::Timeout".to_string(),
+///         }
+///     }
+/// }
+///
+/// let notice = Notice::builder()
+///     .add_named_error(DbError::Timeout)
+///     .build();
+/// assert_eq!("DbError::Timeout", notice.errors[0].name);
+/// ```
+pub trait AirbrakeErrorName {
+    fn airbrake_error_name(&self) -> String;
+}
+
+/// The full name of `E`, or with `short` set its name without module
+/// paths, so `core::num::error::ParseIntError` becomes `ParseIntError`
+pub(crate) fn type_name_of<E: ?Sized>(short: bool) -> String {
+    let name = std::any::type_name::<E>();
+    let name = known_type_name(name).unwrap_or(name);
+    if short {
+        shorten_type_name(name)
+    } else {
+        name.to_string()
+    }
+}
+
+macro_rules! known_errors {
+    ($($error:ty => $name:expr,)*) => {
+        /// The public path of a std error type, which `type_name` reports
+        /// under its private module, e.g. `std::io::error::Error`
+        fn known_type_name(type_name: &str) -> Option<&'static str> {
+            $(
+                if type_name == std::any::type_name::<$error>() {
+                    return Some($name);
+                }
+            )*
+            None
+        }
+
+        /// The public path of an error only known as `dyn Error`, when it is
+        /// one of the std error types
+        fn known_error_name(error: &(dyn Error + 'static)) -> Option<&'static str> {
+            $(
+                if error.is::<$error>() {
+                    return Some($name);
+                }
+            )*
+            None
+        }
+    };
+}
+
+known_errors! {
+    std::io::Error => "std::io::Error",
+    std::fmt::Error => "std::fmt::Error",
+    std::num::ParseIntError => "std::num::ParseIntError",
+    std::num::ParseFloatError => "std::num::ParseFloatError",
+    std::num::TryFromIntError => "std::num::TryFromIntError",
+    std::str::ParseBoolError => "std::str::ParseBoolError",
+    std::str::Utf8Error => "std::str::Utf8Error",
+    std::string::FromUtf8Error => "std::string::FromUtf8Error",
+    std::char::ParseCharError => "std::char::ParseCharError",
+    std::net::AddrParseError => "std::net::AddrParseError",
+    std::env::VarError => "std::env::VarError",
+    std::time::SystemTimeError => "std::time::SystemTimeError",
+    std::sync::mpsc::RecvError => "std::sync::mpsc::RecvError",
+    serde_json::Error => "serde_json::Error",
+}
+
+/// Strips the module path from every type in a type name, including
+/// generic arguments, e.g. `Box<dyn core::error::Error>` to
+/// `Box<dyn Error>`
+pub(crate) fn shorten_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    for (i, c) in name.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            continue;
+        }
+        if c == ':' {
+            segment_start = i + 1;
+            continue;
+        }
+        short.push_str(&name[segment_start..i]);
+        short.push(c);
+        segment_start = i + c.len_utf8();
+    }
+    short.push_str(&name[segment_start..]);
+    short
+}
+
+/// Sources in an error chain are only known as `dyn Error`, so unless
+/// they are a std error type their names are taken from the leading
+/// identifier of their Debug output. This is only a best guess at the
+/// type: Debug output carries no module path, and enums print just the
+/// variant.
+pub(crate) fn debug_name(error: &(dyn Error + 'static)) -> String {
+    if let Some(name) = known_error_name(error) {
+        return name.to_string();
+    }
+    let debug = format!("{:?}", error);
+    let name: String = debug
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == ':')
+        .collect();
+    if name.is_empty() {
+        "Error".to_string()
+    } else {
+        name
+    }
+}
+
+/// anyhow and eyre keep context and ad-hoc messages in private types,
+/// whose Debug output is either the message itself or an `Error` struct
+/// holding the context
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+pub(crate) fn is_private_wrapper(error: &(dyn Error + 'static)) -> bool {
+    if known_error_name(error).is_some() {
+        return false;
+    }
+    let debug = format!("{:?}", error);
+    debug.starts_with("Error { context: ")
+        || debug.starts_with("Error { msg: ")
+        || debug.starts_with('"')
+        || debug == error.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{debug_name, shorten_type_name, type_name_of};
+    use std::fmt;
+    use std::num::ParseIntError;
+
+    #[test]
+    fn type_names_are_shortened() {
+        assert_eq!("ParseIntError", type_name_of::<ParseIntError>(true));
+        assert_eq!(
+            "Box<dyn Error>",
+            shorten_type_name("alloc::boxed::Box<dyn core::error::Error>")
+        );
+        assert_eq!(
+            "Result<(), Error>",
+            shorten_type_name("core::result::Result<(), std::io::error::Error>")
+        );
+    }
+
+    #[test]
+    fn type_names_keep_their_path() {
+        assert_eq!(
+            "std::num::ParseIntError",
+            type_name_of::<ParseIntError>(false)
+        );
+        assert_eq!("std::io::Error", type_name_of::<std::io::Error>(false));
+        assert!(type_name_of::<Quiet>(false).ends_with("::tests::Quiet"));
+    }
+
+    #[test]
+    fn std_errors_are_named_after_their_public_path() {
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "config.toml");
+        assert_eq!("std::io::Error", debug_name(&error));
+        let error = "x".parse::<i32>().unwrap_err();
+        assert_eq!("std::num::ParseIntError", debug_name(&error));
+    }
+
+    #[derive(Debug)]
+    struct Quiet;
+
+    impl fmt::Display for Quiet {
+        fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Ok(())
+        }
+    }
+
+    impl std::error::Error for Quiet {}
+
+    struct Empty;
+
+    impl fmt::Debug for Empty {
+        fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Ok(())
+        }
+    }
+
+    impl fmt::Display for Empty {
+        fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Ok(())
+        }
+    }
+
+    impl std::error::Error for Empty {}
+
+    #[test]
+    fn debug_names_do_not_panic_on_empty_output() {
+        assert_eq!("Quiet", debug_name(&Quiet));
+        assert_eq!("Error", debug_name(&Empty));
+    }
+}
@@ -70,6 +70,10 @@
 //!     .build();
 //! ```
 //!
+//! Resolving the symbols of a backtrace is expensive, so a backtrace captured
+//! with `Backtrace::new_unresolved()` is only resolved by the client when the
+//! notice is about to be sent.
+//!
 //! You can also include a backtrace while building a Notice based on an
 //! error, however since backtraces aren't part of the Error trait, you'll
 //! have to provide it using the add_error_with_backtrace() function:
@@ -132,9 +136,13 @@
 //!
 
 mod error;
+mod error_name;
+#[allow(clippy::module_inception)]
 mod notice;
 mod notice_backtrace;
+mod std_backtrace;
 
 pub use error::{NoticeError, NoticeErrorBuilder};
+pub use error_name::AirbrakeErrorName;
 pub use notice::{Notice, NoticeBuilder};
 pub use notice_backtrace::NoticeFrame;
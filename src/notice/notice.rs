@@ -1,11 +1,16 @@
 use serde_json::{self, Value};
 
-use super::{NoticeError, NoticeFrame};
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+use super::error_name::is_private_wrapper;
+use super::error_name::{shorten_type_name, type_name_of};
+use super::{AirbrakeErrorName, NoticeError, NoticeFrame};
 use crate::{
     backtrace::Backtrace, AirbrakeClient, AirbrakeClientError, Context, ContextBuilder,
-    ContextProperties,
+    ContextProperties, NoticeResponse,
 };
-use log::debug;
+use log::{debug, warn};
+use serde::Serialize;
+use std::backtrace::Backtrace as StdBacktrace;
 use std::collections::HashMap;
 use std::error::Error;
 use std::string::ToString;
@@ -15,9 +20,11 @@ pub struct NoticeBuilder<'a> {
     pub client: Option<&'a AirbrakeClient>,
     pub errors: Vec<NoticeError>,
     pub context: Option<ContextBuilder>,
-    pub environment: Option<HashMap<String, String>>,
-    pub session: Option<HashMap<String, String>>,
-    pub params: Option<HashMap<String, String>>,
+    pub environment: Option<HashMap<String, Value>>,
+    pub session: Option<HashMap<String, Value>>,
+    pub params: Option<HashMap<String, Value>>,
+    pub error_chain_depth: Option<usize>,
+    pub short_error_names: bool,
 }
 
 impl<'a> NoticeBuilder<'a> {
@@ -46,29 +53,151 @@ impl<'a> NoticeBuilder<'a> {
         self
     }
 
+    /// Limit how many NoticeErrors each added error's `source()` chain is
+    /// unrolled into. Applies to errors added after it is set. The error
+    /// itself is always added, so a depth of zero counts as one.
+    pub fn error_chain_depth(&mut self, depth: usize) -> &mut NoticeBuilder<'a> {
+        self.error_chain_depth = Some(depth);
+        self
+    }
+
+    /// Report errors added after it is set without their module paths, e.g.
+    /// `ParseIntError` rather than `std::num::ParseIntError`
+    pub fn short_error_names(&mut self, short: bool) -> &mut NoticeBuilder<'a> {
+        self.short_error_names = short;
+        self
+    }
+
+    fn error_chain(&self, error: &(dyn Error + 'static)) -> Vec<NoticeError> {
+        let mut notice_errors = NoticeError::chain(error, self.error_chain_depth);
+        if self.short_error_names {
+            for notice_error in notice_errors.iter_mut() {
+                notice_error.name = shorten_type_name(&notice_error.name);
+            }
+        }
+        notice_errors
+    }
+
+    fn add_error_chain(
+        &mut self,
+        name: String,
+        error: &(dyn Error + 'static),
+        backtrace: Option<Backtrace>,
+    ) -> &mut NoticeBuilder<'a> {
+        let mut notice_errors = self.error_chain(error);
+        if let Some(notice_error) = notice_errors.first_mut() {
+            notice_error.name = name;
+            match backtrace {
+                Some(b) if NoticeFrame::is_unresolved(&b) => {
+                    notice_error.unresolved_backtrace = Some(b)
+                }
+                Some(b) => notice_error.backtrace_frames = Some(NoticeFrame::from_backtrace(&b)),
+                None => (),
+            }
+        }
+        self.add_notices(notice_errors.into_iter())
+    }
+
     /// Add multiple Errors from an iterator
-    pub fn add_errors<T: Iterator<Item = E>, E: Error>(
+    pub fn add_errors<T: Iterator<Item = E>, E: Error + 'static>(
         &mut self,
         errors: T,
     ) -> &mut NoticeBuilder<'a> {
-        let notice_errors = errors.map(|x| x.into());
-        self.add_notices(notice_errors)
+        for error in errors {
+            self.add_error(error);
+        }
+        self
+    }
+
+    /// Add a single Error, followed by each error in its `source()` chain
+    pub fn add_error<E: Error + 'static>(&mut self, error: E) -> &mut NoticeBuilder<'a> {
+        let name = type_name_of::<E>(self.short_error_names);
+        self.add_error_chain(name, &error, None)
     }
 
-    /// Add a single Error
-    pub fn add_error<E: Error>(&mut self, error: E) -> &mut NoticeBuilder<'a> {
-        let notice_error = NoticeError::from(error);
-        self.add_notice(notice_error)
+    /// Add a single Error, reported with the type name it chooses through
+    /// `AirbrakeErrorName`
+    pub fn add_named_error<E: Error + AirbrakeErrorName + 'static>(
+        &mut self,
+        error: E,
+    ) -> &mut NoticeBuilder<'a> {
+        let name = error.airbrake_error_name();
+        self.add_error_chain(name, &error, None)
     }
 
-    pub fn add_error_with_backtrace<E: Error>(
+    pub fn add_error_with_backtrace<E: Error + 'static>(
         &mut self,
         error: E,
         backtrace: Backtrace,
     ) -> &mut NoticeBuilder<'a> {
-        let mut notice_error = NoticeError::from(error);
-        notice_error.backtrace_frames = Some(NoticeFrame::from_backtrace(&backtrace));
-        self.add_notice(notice_error)
+        let name = type_name_of::<E>(self.short_error_names);
+        self.add_error_chain(name, &error, Some(backtrace))
+    }
+
+    /// Add a single Error along with a backtrace captured by the standard
+    /// library
+    pub fn add_error_with_std_backtrace<E: Error + 'static>(
+        &mut self,
+        error: E,
+        backtrace: &StdBacktrace,
+    ) -> &mut NoticeBuilder<'a> {
+        let first = self.errors.len();
+        self.add_error(error);
+        self.set_backtrace_frames(first, NoticeFrame::from_std_backtrace(backtrace))
+    }
+
+    /// Add an `anyhow::Error`, followed by each error in its chain, with
+    /// the backtrace anyhow captured when it was created
+    #[cfg(feature = "anyhow")]
+    pub fn add_anyhow_error(&mut self, error: &anyhow::Error) -> &mut NoticeBuilder<'a> {
+        let first = self.errors.len();
+        let source: &(dyn Error + 'static) = error.as_ref();
+        let name = type_name_of::<anyhow::Error>(self.short_error_names);
+        self.add_wrapped_error_chain(name, source);
+        self.set_backtrace_frames(first, NoticeFrame::from_std_backtrace(error.backtrace()))
+    }
+
+    /// Add an `eyre::Report`, followed by each error in its chain. eyre's
+    /// handlers don't expose their backtraces, so one is only picked up
+    /// when the handler prints it in the standard library's format.
+    #[cfg(feature = "eyre")]
+    pub fn add_eyre_report(&mut self, report: &eyre::Report) -> &mut NoticeBuilder<'a> {
+        let first = self.errors.len();
+        let source: &(dyn Error + 'static) = report.as_ref();
+        let name = type_name_of::<eyre::Report>(self.short_error_names);
+        self.add_wrapped_error_chain(name, source);
+        let debug = format!("{:?}", report);
+        let frames = debug
+            .find("Stack backtrace:")
+            .map(|index| NoticeFrame::parse_std_backtrace(&debug[index..]))
+            .unwrap_or_default();
+        self.set_backtrace_frames(first, frames)
+    }
+
+    /// The outermost error of an anyhow or eyre chain is named like the
+    /// rest of the chain, unless it is one of their private context or
+    /// message types, which are named after the wrapper instead
+    #[cfg(any(feature = "anyhow", feature = "eyre"))]
+    fn add_wrapped_error_chain(&mut self, name: String, source: &(dyn Error + 'static)) {
+        let mut notice_errors = self.error_chain(source);
+        if let Some(notice_error) = notice_errors
+            .first_mut()
+            .filter(|_| is_private_wrapper(source))
+        {
+            notice_error.name = name;
+        }
+        self.add_notices(notice_errors.into_iter());
+    }
+
+    fn set_backtrace_frames(
+        &mut self,
+        index: usize,
+        frames: Vec<NoticeFrame>,
+    ) -> &mut NoticeBuilder<'a> {
+        if let Some(notice_error) = self.errors.get_mut(index).filter(|_| !frames.is_empty()) {
+            notice_error.backtrace_frames = Some(frames);
+        }
+        self
     }
 
     /// Set the context on the NoticeBuilder
@@ -78,8 +207,11 @@ impl<'a> NoticeBuilder<'a> {
     }
 
     /// Set the environment on the NoticeBuilder
-    pub fn environment(&mut self, environment: HashMap<String, String>) -> &mut NoticeBuilder<'a> {
-        self.environment = Some(environment);
+    pub fn environment<V: Into<Value>>(
+        &mut self,
+        environment: HashMap<String, V>,
+    ) -> &mut NoticeBuilder<'a> {
+        self.environment = Some(into_values(environment));
         self
     }
 
@@ -92,20 +224,33 @@ impl<'a> NoticeBuilder<'a> {
     ///     .build();
     /// ```
     pub fn add_environment(&mut self, key: &str, value: &str) -> &mut NoticeBuilder<'a> {
-        self.environment = self
-            .environment
-            .clone()
-            .or_else(|| Some(HashMap::new()))
-            .and_then(|mut h| {
-                h.insert(key.to_string(), value.to_string());
-                Some(h)
-            });
+        insert_value(&mut self.environment, key, Value::from(value));
+        self
+    }
+
+    /// Add environment to the NoticeBuilder from any serializable value,
+    /// which is sent as JSON rather than a string
+    pub fn add_environment_value<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> &mut NoticeBuilder<'a> {
+        match serde_json::to_value(value) {
+            Ok(value) => insert_value(&mut self.environment, key, value),
+            Err(e) => warn!(
+                "Airbrake environment {} could not be serialized: {}",
+                key, e
+            ),
+        }
         self
     }
 
     /// Set the environment on the NoticeBuilder
-    pub fn session(&mut self, session: HashMap<String, String>) -> &mut NoticeBuilder<'a> {
-        self.session = Some(session);
+    pub fn session<V: Into<Value>>(
+        &mut self,
+        session: HashMap<String, V>,
+    ) -> &mut NoticeBuilder<'a> {
+        self.session = Some(into_values(session));
         self
     }
 
@@ -118,20 +263,27 @@ impl<'a> NoticeBuilder<'a> {
     ///     .build();
     /// ```
     pub fn add_session(&mut self, key: &str, value: &str) -> &mut NoticeBuilder<'a> {
-        self.session = self
-            .session
-            .clone()
-            .or_else(|| Some(HashMap::new()))
-            .and_then(|mut h| {
-                h.insert(key.to_string(), value.to_string());
-                Some(h)
-            });
+        insert_value(&mut self.session, key, Value::from(value));
+        self
+    }
+
+    /// Add session to the NoticeBuilder from any serializable value, which is
+    /// sent as JSON rather than a string
+    pub fn add_session_value<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> &mut NoticeBuilder<'a> {
+        match serde_json::to_value(value) {
+            Ok(value) => insert_value(&mut self.session, key, value),
+            Err(e) => warn!("Airbrake session {} could not be serialized: {}", key, e),
+        }
         self
     }
 
     /// Set the environment on the NoticeBuilder
-    pub fn params(&mut self, params: HashMap<String, String>) -> &mut NoticeBuilder<'a> {
-        self.params = Some(params);
+    pub fn params<V: Into<Value>>(&mut self, params: HashMap<String, V>) -> &mut NoticeBuilder<'a> {
+        self.params = Some(into_values(params));
         self
     }
 
@@ -145,14 +297,29 @@ impl<'a> NoticeBuilder<'a> {
     ///     .build();
     /// ```
     pub fn add_param(&mut self, key: &str, value: &str) -> &mut NoticeBuilder<'a> {
-        self.params = self
-            .params
-            .clone()
-            .or_else(|| Some(HashMap::new()))
-            .and_then(|mut h| {
-                h.insert(key.to_string(), value.to_string());
-                Some(h)
-            });
+        insert_value(&mut self.params, key, Value::from(value));
+        self
+    }
+
+    /// Add param to the NoticeBuilder from any serializable value, which is
+    /// sent as JSON rather than a string
+    /// ```
+    /// use airbrake::Notice;
+    /// use serde_json::json;
+    /// let notice = Notice::builder()
+    ///     .add_param_value("cart", &json!({"items": 3, "total": 42.5}))
+    ///     .add_param_value("page", &3)
+    ///     .build();
+    /// ```
+    pub fn add_param_value<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> &mut NoticeBuilder<'a> {
+        match serde_json::to_value(value) {
+            Ok(value) => insert_value(&mut self.params, key, value),
+            Err(e) => warn!("Airbrake param {} could not be serialized: {}", key, e),
+        }
         self
     }
 
@@ -200,7 +367,7 @@ impl<'a> From<&ContextBuilder> for NoticeBuilder<'a> {
     }
 }
 
-impl<'a, E: Error> From<E> for NoticeBuilder<'a> {
+impl<'a, E: Error + 'static> From<E> for NoticeBuilder<'a> {
     fn from(error: E) -> NoticeBuilder<'a> {
         let mut notice = NoticeBuilder::new();
         notice.add_error(error);
@@ -208,6 +375,15 @@ impl<'a, E: Error> From<E> for NoticeBuilder<'a> {
     }
 }
 
+fn into_values<V: Into<Value>>(map: HashMap<String, V>) -> HashMap<String, Value> {
+    map.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+fn insert_value(map: &mut Option<HashMap<String, Value>>, key: &str, value: Value) {
+    map.get_or_insert_with(HashMap::new)
+        .insert(key.to_string(), value);
+}
+
 #[derive(Debug, Serialize)]
 pub struct Notice<'a> {
     #[serde(skip)]
@@ -219,13 +395,13 @@ pub struct Notice<'a> {
     pub context: Option<Context>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub environment: Option<HashMap<String, String>>,
+    pub environment: Option<HashMap<String, Value>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub session: Option<HashMap<String, String>>,
+    pub session: Option<HashMap<String, Value>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub params: Option<HashMap<String, String>>,
+    pub params: Option<HashMap<String, Value>>,
 }
 
 impl<'a> Notice<'a> {
@@ -243,6 +419,7 @@ impl<'a> Notice<'a> {
         NoticeBuilder::new()
     }
 
+    /// Queues the notice for delivery by the client's background workers
     pub fn send(self) -> Result<(), AirbrakeClientError> {
         match self.client {
             Some(c) => {
@@ -252,6 +429,31 @@ impl<'a> Notice<'a> {
             None => Err(AirbrakeClientError::NoticeClientNotSet),
         }
     }
+
+    /// Sends the notice on the current thread, waiting for the request
+    /// to complete
+    pub fn send_sync(self) -> Result<NoticeResponse, AirbrakeClientError> {
+        match self.client {
+            Some(c) => {
+                debug!("Sending synchronously via notice client");
+                c.notify_sync(self)
+            }
+            None => Err(AirbrakeClientError::NoticeClientNotSet),
+        }
+    }
+
+    /// Drops the client reference so the notice can be handed to another
+    /// thread
+    pub(crate) fn detach(self) -> Notice<'static> {
+        Notice {
+            client: None,
+            errors: self.errors,
+            context: self.context,
+            environment: self.environment,
+            session: self.session,
+            params: self.params,
+        }
+    }
 }
 
 impl<'a> From<Notice<'a>> for Value {
@@ -369,6 +571,168 @@ mod tests {
         assert_eq!(Value::from_str(expected_json).unwrap(), Value::from(notice));
     }
 
+    #[test]
+    fn notice_with_add_param_value() {
+        #[derive(Serialize)]
+        struct Cart {
+            items: Vec<&'static str>,
+            total: f64,
+        }
+
+        let cart = Cart {
+            items: vec!["apple"],
+            total: 1.5,
+        };
+        let notice = Notice::builder()
+            .add_param_value("cart", &cart)
+            .add_param_value("page", &3)
+            .add_session_value("admin", &true)
+            .add_environment_value("workers", &4)
+            .build();
+        let expected_json = r#"
+        {
+            "errors": [],
+            "params": {
+                "cart": {
+                    "items": ["apple"],
+                    "total": 1.5
+                },
+                "page": 3
+            },
+            "session": {
+                "admin": true
+            },
+            "environment": {
+                "workers": 4
+            }
+        }
+        "#;
+        assert_eq!(Value::from_str(expected_json).unwrap(), Value::from(notice));
+    }
+
+    #[test]
+    fn zero_error_chain_depth_keeps_the_error() {
+        let error = "x".parse::<i32>().unwrap_err();
+        let notice = Notice::builder()
+            .error_chain_depth(0)
+            .add_error(error)
+            .build();
+        assert_eq!(1, notice.errors.len());
+    }
+
+    #[test]
+    fn notice_error_named_after_type() {
+        let error = "x".parse::<i32>().unwrap_err();
+        let notice = Notice::builder().add_error(error.clone()).build();
+        assert_eq!("std::num::ParseIntError", notice.errors[0].name);
+
+        let notice = Notice::builder()
+            .short_error_names(true)
+            .add_error(error)
+            .build();
+        assert_eq!("ParseIntError", notice.errors[0].name);
+    }
+
+    #[test]
+    fn notice_error_with_std_backtrace() {
+        let error = "x".parse::<i32>().unwrap_err();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let notice = Notice::builder()
+            .add_error_with_std_backtrace(error, &backtrace)
+            .build();
+
+        let frames = notice.errors[0].backtrace_frames.as_ref().unwrap();
+        assert!(frames.iter().any(|frame| frame
+            .function
+            .as_deref()
+            .is_some_and(|f| f.contains("notice_error_with_std_backtrace"))));
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn notice_from_anyhow_error() {
+        use anyhow::Context as _;
+
+        let error = "x".parse::<i32>().context("invalid port").unwrap_err();
+        let notice = Notice::builder().add_anyhow_error(&error).build();
+        let messages: Vec<&str> = notice
+            .errors
+            .iter()
+            .map(|e| e.message.as_deref().unwrap())
+            .collect();
+
+        assert_eq!(
+            vec!["invalid port", "invalid digit found in string"],
+            messages
+        );
+        assert_eq!("anyhow::Error", notice.errors[0].name);
+        assert_eq!("std::num::ParseIntError", notice.errors[1].name);
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow_error_without_context_keeps_its_type() {
+        let error = anyhow::Error::from("x".parse::<i32>().unwrap_err());
+        let notice = Notice::builder().add_anyhow_error(&error).build();
+        assert_eq!(1, notice.errors.len());
+        assert_eq!("std::num::ParseIntError", notice.errors[0].name);
+
+        let notice = Notice::builder()
+            .add_anyhow_error(&anyhow::anyhow!("invalid port {}", 0))
+            .build();
+        assert_eq!("anyhow::Error", notice.errors[0].name);
+    }
+
+    #[cfg(feature = "eyre")]
+    #[test]
+    fn notice_from_eyre_report() {
+        use eyre::WrapErr;
+
+        let report = "x".parse::<i32>().wrap_err("invalid port").unwrap_err();
+        let notice = Notice::builder().add_eyre_report(&report).build();
+
+        assert_eq!(2, notice.errors.len());
+        assert_eq!("eyre::Report", notice.errors[0].name);
+        assert_eq!(Some("invalid port"), notice.errors[0].message.as_deref());
+    }
+
+    #[cfg(feature = "eyre")]
+    #[derive(Debug)]
+    struct BacktraceHandler {
+        backtrace: std::backtrace::Backtrace,
+    }
+
+    #[cfg(feature = "eyre")]
+    impl eyre::EyreHandler for BacktraceHandler {
+        fn debug(
+            &self,
+            error: &(dyn std::error::Error + 'static),
+            f: &mut std::fmt::Formatter<'_>,
+        ) -> std::fmt::Result {
+            write!(f, "{}\n\nStack backtrace:\n{}", error, self.backtrace)
+        }
+    }
+
+    #[cfg(feature = "eyre")]
+    #[test]
+    fn eyre_report_backtrace_is_read_from_debug_output() {
+        // The hook can only be installed once per process
+        let _ = eyre::set_hook(Box::new(|_| {
+            Box::new(BacktraceHandler {
+                backtrace: std::backtrace::Backtrace::force_capture(),
+            })
+        }));
+
+        let report = eyre::eyre!("invalid port");
+        let notice = Notice::builder().add_eyre_report(&report).build();
+
+        let frames = notice.errors[0].backtrace_frames.as_ref().unwrap();
+        assert!(frames.iter().any(|frame| frame
+            .function
+            .as_deref()
+            .is_some_and(|f| f.contains("eyre_report_backtrace_is_read_from_debug_output"))));
+    }
+
     #[test]
     fn notice_context_default() {
         let context = Context::builder();
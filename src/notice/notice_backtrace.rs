@@ -1,7 +1,8 @@
 use crate::backtrace::{Backtrace, BacktraceFrame, BacktraceSymbol};
+use crate::demangle::demangled_name;
 use std::collections::HashMap;
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Default)]
 pub struct NoticeFrame {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file: Option<String>,
@@ -9,16 +10,22 @@ pub struct NoticeFrame {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line: Option<u32>,
 
-    /// TODO: Support for column number not yet implemented
+    /// Column within `line`, when the debug info records one
     #[serde(skip_serializing_if = "Option::is_none")]
     pub column: Option<u32>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function: Option<String>,
 
-    /// TODO: Support for surrounding code not implemented
+    /// Lines of source code around `line`, keyed by line number
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<HashMap<i32, String>>,
+
+    /// Whether the frame belongs to the project rather than a dependency or
+    /// the standard library, filled in by the client before notice filters
+    /// and `drop_frames` predicates run
+    #[serde(skip)]
+    pub in_project: Option<bool>,
 }
 
 impl NoticeFrame {
@@ -26,33 +33,39 @@ impl NoticeFrame {
     /// different scopes. The frame is a context of a single line, but that
     /// single line may have multiple scopes. The Backtrace/Airbrake relationship
     /// is 1-to-1 between a Backtrace Symbols (not Backtrace Frame) and a Airbrake Frame.
-    fn unroll_frame_symbols(frame: &BacktraceFrame) -> Vec<NoticeFrame> {
+    pub(crate) fn unroll_frame_symbols(frame: &BacktraceFrame) -> Vec<NoticeFrame> {
         frame.symbols().iter().map(NoticeFrame::from).collect()
     }
 
     pub fn from_backtrace(backtrace: &Backtrace) -> Vec<NoticeFrame> {
         let mut frames: Vec<NoticeFrame> = vec![];
         for f in backtrace.frames() {
-            frames.append(&mut NoticeFrame::unroll_frame_symbols(&f));
+            frames.append(&mut NoticeFrame::unroll_frame_symbols(f));
         }
         frames
     }
+
+    /// Whether the backtrace was captured without resolving its symbols
+    pub(crate) fn is_unresolved(backtrace: &Backtrace) -> bool {
+        let frames = backtrace.frames();
+        !frames.is_empty() && frames.iter().all(|frame| frame.symbols().is_empty())
+    }
 }
 
 impl From<&BacktraceSymbol> for NoticeFrame {
     fn from(symbol: &BacktraceSymbol) -> NoticeFrame {
         let filename = symbol
             .filename()
-            .map(|sfn| sfn.to_str())
-            .map(|sfn| sfn.unwrap().to_string());
-        let function_name = symbol.name().map(|sn| sn.to_string());
+            .map(|sfn| sfn.to_string_lossy().into_owned());
+        let function_name = symbol.name().map(|sn| demangled_name(&sn));
 
         NoticeFrame {
             file: filename,
             line: symbol.lineno(),
-            column: None,
+            column: symbol.colno(),
             function: function_name,
             code: None,
+            in_project: None,
         }
     }
 }
@@ -81,12 +94,13 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::redundant_closure, clippy::redundant_closure_call)]
     fn backtrace_unrolls_multiple_symboles() {
         let function_name: String = "backtrace_unrolls_multiple_symboles".to_string();
-        let nested_frame_line: u32 = 89;
         // This backtrace is generated from within a nested enclosure so
         // that the backtraces creates a single frame with two symboles
         let fn_backtrace = || (|| Backtrace::new())();
+        let nested_frame_line = line!() - 1;
         let backtrace = fn_backtrace();
         let selected_frames: Vec<NoticeFrame> = NoticeFrame::from_backtrace(&backtrace)
             .into_iter()
@@ -98,4 +112,33 @@ mod tests {
 
         assert_gt!(selected_frames.len(), 1);
     }
+
+    #[test]
+    fn backtrace_frames_include_columns() {
+        let function_name: String = "backtrace_frames_include_columns".to_string();
+        // `Backtrace::new()` starts right after `column!(), `
+        let (column, backtrace) = (column!(), Backtrace::new());
+        let column = column + "column!(), ".len() as u32;
+        let frame = NoticeFrame::from_backtrace(&backtrace)
+            .into_iter()
+            .find(|frame| match frame.function.as_ref() {
+                Some(inner) => inner.contains(&function_name),
+                None => false,
+            })
+            .unwrap();
+
+        assert_eq!(Some(column), frame.column);
+    }
+
+    #[test]
+    fn backtrace_function_names_have_no_hash() {
+        let backtrace = Backtrace::new();
+        let function = NoticeFrame::from_backtrace(&backtrace)
+            .into_iter()
+            .filter_map(|frame| frame.function)
+            .find(|function| function.contains("backtrace_function_names_have_no_hash"))
+            .unwrap();
+
+        assert!(function.ends_with("tests::backtrace_function_names_have_no_hash"));
+    }
 }
@@ -0,0 +1,92 @@
+use regex::Regex;
+use std::backtrace::{Backtrace as StdBacktrace, BacktraceStatus};
+use std::sync::LazyLock;
+
+use super::NoticeFrame;
+use crate::demangle::strip_hash;
+
+static FUNCTION_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*\d+: (.+)$").unwrap());
+static LOCATION_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*at (.+?):(\d+)(?::(\d+))?$").unwrap());
+
+impl NoticeFrame {
+    /// Converts a backtrace captured by the standard library. Its frames
+    /// aren't accessible on stable Rust, so they are parsed from its
+    /// printed form.
+    pub fn from_std_backtrace(backtrace: &StdBacktrace) -> Vec<NoticeFrame> {
+        match backtrace.status() {
+            BacktraceStatus::Captured => NoticeFrame::parse_std_backtrace(&backtrace.to_string()),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Parses backtraces printed in the standard library's format:
+    ///
+    /// ```text
+    ///    0: my_app::main
+    ///              at ./src/main.rs:10:5
+    /// ```
+    pub(crate) fn parse_std_backtrace(text: &str) -> Vec<NoticeFrame> {
+        let mut frames: Vec<NoticeFrame> = Vec::new();
+        for line in text.lines() {
+            if let Some(captures) = FUNCTION_LINE.captures(line) {
+                frames.push(NoticeFrame {
+                    function: Some(strip_hash(captures[1].trim()).to_string()),
+                    ..NoticeFrame::default()
+                });
+            } else if let Some(captures) = LOCATION_LINE.captures(line) {
+                if let Some(frame) = frames.last_mut().filter(|frame| frame.file.is_none()) {
+                    let file = &captures[1];
+                    frame.file = Some(file.strip_prefix("./").unwrap_or(file).to_string());
+                    frame.line = captures[2].parse().ok();
+                    frame.column = captures
+                        .get(3)
+                        .and_then(|column| column.as_str().parse().ok());
+                }
+            }
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::NoticeFrame;
+    use std::backtrace::Backtrace;
+
+    #[test]
+    fn std_backtrace_text_is_parsed() {
+        let text = "   0: my_app::load_config::h1a2b3c4d5e6f7a8b
+             at ./src/config.rs:12:9
+   1: my_app::main
+             at ./src/main.rs:3:21
+   2: main
+   3: __libc_start_main
+";
+        let frames = NoticeFrame::parse_std_backtrace(text);
+
+        assert_eq!(4, frames.len());
+        assert_eq!(Some("my_app::load_config"), frames[0].function.as_deref());
+        assert_eq!(Some("src/config.rs"), frames[0].file.as_deref());
+        assert_eq!(Some(12), frames[0].line);
+        assert_eq!(Some(9), frames[0].column);
+        assert_eq!(Some("main"), frames[2].function.as_deref());
+        assert_eq!(None, frames[2].file);
+    }
+
+    #[test]
+    fn captured_std_backtrace_contains_current_function() {
+        let backtrace = Backtrace::force_capture();
+        let frames = NoticeFrame::from_std_backtrace(&backtrace);
+
+        assert!(frames.iter().any(|frame| frame
+            .function
+            .as_deref()
+            .is_some_and(|f| f.contains("captured_std_backtrace_contains_current_function"))));
+    }
+
+    #[test]
+    fn disabled_std_backtrace_has_no_frames() {
+        assert!(NoticeFrame::from_std_backtrace(&Backtrace::disabled()).is_empty());
+    }
+}
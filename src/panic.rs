@@ -0,0 +1,223 @@
+use std::fmt;
+use std::panic::{self, PanicHookInfo};
+use std::sync::Arc;
+use std::thread;
+
+use crate::{ContextBuilder, ContextProperties, NoticeFrame};
+
+/// Functions of the panic machinery that run between the panic site and
+/// the hook
+const PANIC_FRAME_PREFIXES: &[&str] = &[
+    "std::panicking::",
+    "core::panicking::",
+    "std::panic::",
+    "core::panic::",
+    "rust_begin_unwind",
+    "__rustc::rust_begin_unwind",
+    "std::sys_common::backtrace::__rust_end_short_backtrace",
+    "std::sys::backtrace::__rust_end_short_backtrace",
+];
+
+pub(crate) type PanicHook = dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static;
+
+/// Serialises tests that replace the process-wide panic hook
+#[cfg(test)]
+pub(crate) static HOOK_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Returned by `AirbrakeClient::install_panic_hook`. Dropping it puts the
+/// panic hook that was installed before back in place.
+#[must_use = "the previous panic hook is restored as soon as the guard is dropped"]
+pub struct PanicHookGuard {
+    previous: Option<Arc<PanicHook>>,
+}
+
+impl PanicHookGuard {
+    pub(crate) fn new(previous: Arc<PanicHook>) -> PanicHookGuard {
+        PanicHookGuard {
+            previous: Some(previous),
+        }
+    }
+}
+
+impl fmt::Debug for PanicHookGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicHookGuard").finish()
+    }
+}
+
+impl Drop for PanicHookGuard {
+    fn drop(&mut self) {
+        // The hook can't be replaced while unwinding, and the process is
+        // usually on its way out at that point anyway
+        if thread::panicking() {
+            return;
+        }
+        if let Some(previous) = self.previous.take() {
+            panic::set_hook(Box::new(move |panic_info| previous(panic_info)));
+        }
+    }
+}
+
+/// Panics raised with a literal carry a `&str` payload, formatted ones
+/// carry a `String`
+pub(crate) fn panic_message(panic_info: &PanicHookInfo<'_>) -> Option<String> {
+    let payload = panic_info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+}
+
+/// The panic site and the panicking thread, reported as notice context
+pub(crate) fn panic_context(panic_info: &PanicHookInfo<'_>) -> ContextBuilder {
+    let mut context = ContextBuilder::new();
+    if let Some(location) = panic_info.location() {
+        context.panic_location(&format!(
+            "{}:{}:{}",
+            location.file(),
+            location.line(),
+            location.column()
+        ));
+    }
+    let thread = thread::current();
+    if let Some(name) = thread.name() {
+        context.thread_name(name);
+    }
+    context.thread_id(&format!("{:?}", thread.id()));
+    context
+}
+
+/// Drops the frames of the backtrace that belong to the hook and the panic
+/// machinery, so the first remaining frame is the panic site. Only the
+/// first run of machinery frames counts, since `catch_unwind` frames
+/// further down wrap every thread's stack.
+pub(crate) fn strip_panic_frames(frames: Vec<NoticeFrame>) -> Vec<NoticeFrame> {
+    let is_panic_frame = |frame: &NoticeFrame| {
+        frame.function.as_ref().is_some_and(|function| {
+            PANIC_FRAME_PREFIXES
+                .iter()
+                .any(|prefix| function.starts_with(prefix))
+        })
+    };
+    let first_panic_frame = match frames.iter().position(is_panic_frame) {
+        Some(index) => index,
+        None => return frames,
+    };
+    let panic_site = frames[first_panic_frame..]
+        .iter()
+        .position(|frame| !is_panic_frame(frame))
+        .map_or(frames.len(), |index| first_panic_frame + index);
+    frames.into_iter().skip(panic_site).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{panic_context, panic_message, strip_panic_frames};
+    use crate::backtrace::Backtrace;
+    use crate::NoticeFrame;
+    use std::panic;
+    use std::sync::{Arc, Mutex};
+
+    fn frame(function: &str) -> NoticeFrame {
+        NoticeFrame {
+            function: Some(function.to_string()),
+            ..NoticeFrame::default()
+        }
+    }
+
+    #[test]
+    fn strips_frames_up_to_the_panic_machinery() {
+        let frames = vec![
+            frame("backtrace::capture::Backtrace::new"),
+            frame("airbrake::client::AirbrakeClient::panic_hook::{{closure}}"),
+            frame("std::panicking::rust_panic_with_hook"),
+            frame("core::panicking::panic_fmt"),
+            frame("my_app::main"),
+        ];
+        let functions: Vec<String> = strip_panic_frames(frames)
+            .into_iter()
+            .filter_map(|f| f.function)
+            .collect();
+        assert_eq!(vec!["my_app::main"], functions);
+    }
+
+    #[test]
+    fn keeps_frames_below_the_panic_site() {
+        let frames = vec![
+            frame("airbrake::client::AirbrakeClient::panic_hook::{{closure}}"),
+            frame("std::panicking::rust_panic_with_hook"),
+            frame("core::panicking::panic_fmt"),
+            frame("my_app::main"),
+            frame("std::panicking::try"),
+            frame("std::panic::catch_unwind"),
+            frame("std::rt::lang_start_internal"),
+        ];
+        let functions: Vec<String> = strip_panic_frames(frames)
+            .into_iter()
+            .filter_map(|f| f.function)
+            .collect();
+        assert_eq!(
+            vec![
+                "my_app::main",
+                "std::panicking::try",
+                "std::panic::catch_unwind",
+                "std::rt::lang_start_internal"
+            ],
+            functions
+        );
+    }
+
+    #[inline(never)]
+    fn panicking_function() {
+        panic!("boom");
+    }
+
+    #[test]
+    fn real_panic_site_is_the_first_frame() {
+        let _lock = super::HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let captured = Arc::new(Mutex::new(None));
+        let hook_captured = Arc::clone(&captured);
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |_| {
+            let frames = NoticeFrame::from_backtrace(&Backtrace::new());
+            *hook_captured.lock().unwrap() = Some(strip_panic_frames(frames));
+        }));
+        let _ = panic::catch_unwind(panicking_function);
+        panic::set_hook(previous_hook);
+
+        let frames = captured.lock().unwrap().take().unwrap();
+        let function = frames[0].function.as_deref().unwrap();
+        assert!(
+            function.ends_with("panic::tests::panicking_function"),
+            "unexpected first frame {}",
+            function
+        );
+    }
+
+    #[test]
+    fn keeps_frames_without_panic_machinery() {
+        let frames = vec![frame("my_app::run"), frame("my_app::main")];
+        assert_eq!(2, strip_panic_frames(frames).len());
+    }
+
+    #[test]
+    fn captures_string_payloads_and_location() {
+        let _lock = super::HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let captured = Arc::new(Mutex::new(None));
+        let hook_captured = Arc::clone(&captured);
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |panic_info| {
+            *hook_captured.lock().unwrap() =
+                Some((panic_message(panic_info), panic_context(panic_info).build()));
+        }));
+        let line = line!() + 1;
+        let _ = panic::catch_unwind(|| panic!("formatted {}", 42));
+        panic::set_hook(previous_hook);
+
+        let (message, context) = captured.lock().unwrap().take().unwrap();
+        assert_eq!(Some("formatted 42".to_string()), message);
+        let location = context.panic_location.unwrap();
+        assert!(location.starts_with(&format!("src/panic.rs:{}:", line)));
+        assert!(context.thread_id.is_some());
+    }
+}
@@ -0,0 +1,205 @@
+use reqwest::StatusCode;
+use std::time::{Duration, Instant};
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitState};
+use crate::client::{endpoint_uri, AirbrakeClientBuilderError};
+use crate::filter::NoticeFilters;
+use crate::frames::FrameProcessing;
+use crate::rate_limit::RateLimit;
+use crate::redaction::KeyRedaction;
+use crate::response::parse_response;
+use crate::retry::RetryPolicy;
+use crate::scope::apply_scope;
+use crate::{
+    AirbrakeClientBuilder, AirbrakeClientError, Context, Notice, NoticeBuilder, NoticeResponse,
+};
+
+/// Builds a reqwest client from the builder's proxy and timeout settings.
+/// The blocking and async reqwest clients have builders with the same
+/// methods, but no trait in common.
+macro_rules! build_http_client {
+    ($http_builder:expr, $settings:expr) => {{
+        let settings: &$crate::AirbrakeClientBuilder = $settings;
+        let mut builder = $http_builder;
+        if let Some(proxy) = settings.proxy_settings()? {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(timeout) = settings.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = settings.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        builder
+            .build()
+            .map_err(|e| $crate::client::AirbrakeClientBuilderError::HttpClient(e.to_string()))
+    }};
+}
+
+pub(crate) use build_http_client;
+
+/// Everything a client does with a notice apart from the HTTP request
+/// itself, shared by the blocking and the async client
+#[derive(Debug, Clone)]
+pub(crate) struct NoticePipeline {
+    endpoint: String,
+    pub(crate) context: Option<Context>,
+    filters: NoticeFilters,
+    redaction: KeyRedaction,
+    pub(crate) frames: FrameProcessing,
+    rate_limit: RateLimit,
+    circuit_breaker: Option<CircuitBreaker>,
+    retry: RetryPolicy,
+}
+
+impl NoticePipeline {
+    pub(crate) fn new(
+        builder: &AirbrakeClientBuilder,
+    ) -> Result<NoticePipeline, AirbrakeClientBuilderError> {
+        let (project_id, project_key) = builder.project_settings()?;
+        Ok(NoticePipeline {
+            endpoint: endpoint_uri(&builder.host_or_default(), &project_id, &project_key),
+            context: builder.context.clone().map(|c| c.build()),
+            filters: builder.filters.clone(),
+            redaction: builder.redaction.clone(),
+            frames: builder.frames.clone(),
+            rate_limit: RateLimit::default(),
+            circuit_breaker: builder.circuit_breaker_settings(),
+            retry: builder.retry_policy(),
+        })
+    }
+
+    pub(crate) fn endpoint_uri(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// A notice builder carrying the client's context and the active scope
+    pub(crate) fn new_notice_builder(&self) -> NoticeBuilder<'_> {
+        let mut notice_builder = match &self.context {
+            Some(context) => context.new_notice_builder(),
+            None => Notice::builder(),
+        };
+        apply_scope(&mut notice_builder);
+        notice_builder
+    }
+
+    /// Fails fast while rate limited or while the circuit breaker is open
+    pub(crate) fn check(&self) -> Result<(), AirbrakeClientError> {
+        self.rate_limit.check()?;
+        match &self.circuit_breaker {
+            Some(breaker) => breaker.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Fills in the client context, runs the filters over the notice and
+    /// redacts sensitive keys
+    pub(crate) fn prepare<'a>(
+        &self,
+        mut notice: Notice<'a>,
+    ) -> Result<Notice<'a>, AirbrakeClientError> {
+        // TODO: This is a codesmell- the notify function shouldn't be
+        // mutating the notice. Testing this is very difficult. Too
+        // difficult for me to bother figuring out, which means this is
+        // poorly designed
+        notice.context = notice.context.or_else(|| self.context.clone());
+        self.frames.mark_frames(&mut notice);
+        self.filters.apply(&mut notice)?;
+        self.redaction.apply(&mut notice);
+        Ok(notice)
+    }
+
+    /// Asks the rate limit and the circuit breaker for permission to send
+    /// a request. Its outcome has to be passed to `record`.
+    pub(crate) fn acquire(&self) -> Result<(), AirbrakeClientError> {
+        self.rate_limit.check()?;
+        match &self.circuit_breaker {
+            Some(breaker) => breaker.acquire(),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn record(&self, result: &Result<NoticeResponse, AirbrakeClientError>) {
+        if let Some(breaker) = &self.circuit_breaker {
+            breaker.record(result);
+        }
+    }
+
+    /// Interprets the status, rate limit delay and body that Airbrake
+    /// responded with
+    pub(crate) fn classify<E>(
+        &self,
+        response: Result<(StatusCode, Option<Duration>, String), E>,
+    ) -> Result<NoticeResponse, AirbrakeClientError>
+    where
+        AirbrakeClientError: From<E>,
+    {
+        response
+            .map_err(AirbrakeClientError::from)
+            .and_then(|(status, delay, body)| match delay {
+                Some(delay) if status == StatusCode::TOO_MANY_REQUESTS => {
+                    Err(self.rate_limit.limit_for(delay))
+                }
+                _ => parse_response(status, &body),
+            })
+    }
+
+    pub(crate) fn attempts(&self) -> Attempts<'_> {
+        Attempts {
+            retry: &self.retry,
+            attempts: 0,
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Reports the state of the circuit breaker, which is always closed
+    /// when no breaker was configured
+    pub(crate) fn circuit_state(&self) -> CircuitState {
+        self.circuit_breaker
+            .as_ref()
+            .map_or(CircuitState::Closed, CircuitBreaker::state)
+    }
+}
+
+/// Counts the attempts at sending one notice and decides whether a failed
+/// attempt is retried
+pub(crate) struct Attempts<'a> {
+    retry: &'a RetryPolicy,
+    attempts: u32,
+    start_time: Instant,
+}
+
+impl Attempts<'_> {
+    /// Returns how long to wait before retrying, or `None` once the result
+    /// is final
+    pub(crate) fn backoff(
+        &mut self,
+        result: &Result<NoticeResponse, AirbrakeClientError>,
+    ) -> Option<Duration> {
+        self.attempts += 1;
+        let backoff = result
+            .as_ref()
+            .err()
+            .and_then(|e| self.retry.next_backoff(self.attempts, e))?;
+        debug!(
+            "Airbrake notify attempt {} failed, retrying in {:?}",
+            self.attempts, backoff
+        );
+        Some(backoff)
+    }
+
+    pub(crate) fn finish(
+        self,
+        result: Result<NoticeResponse, AirbrakeClientError>,
+    ) -> Result<NoticeResponse, AirbrakeClientError> {
+        debug!(
+            "Airbrake notify request took: {:?} ({} attempts)",
+            self.start_time.elapsed(),
+            self.attempts
+        );
+        result.map_err(|e| {
+            warn!("Airbrake notification failed: {:?}", e);
+            e
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use reqwest::{Proxy, Url};
+
+use crate::client::AirbrakeClientBuilderError;
+
+/// Builds the proxy used by both the blocking and async HTTP clients.
+///
+/// Proxies without a scheme are assumed to be HTTP proxies. Credentials
+/// are stored on the proxy URL itself, which reqwest understands for every
+/// proxy scheme, including socks5 when the `socks` feature is enabled.
+pub(crate) fn build_proxy(
+    proxy: &str,
+    auth: Option<&(String, String)>,
+    no_proxy: &[String],
+) -> Result<Proxy, AirbrakeClientBuilderError> {
+    let proxy_url = if proxy.contains("://") {
+        proxy.to_string()
+    } else {
+        format!("http://{}", proxy)
+    };
+    let mut proxy_url = Url::parse(&proxy_url)
+        .map_err(|e| AirbrakeClientBuilderError::InvalidProxy(e.to_string()))?;
+    if let Some((username, password)) = auth {
+        proxy_url
+            .set_username(username)
+            .and_then(|_| proxy_url.set_password(Some(password)))
+            .map_err(|_| {
+                AirbrakeClientBuilderError::InvalidProxy("proxy URL can't have credentials".into())
+            })?;
+    }
+
+    // Proxy::all rejects schemes reqwest doesn't know how to proxy through,
+    // so it's used purely for validation here
+    Proxy::all(proxy_url.clone())
+        .map_err(|e| AirbrakeClientBuilderError::InvalidProxy(e.to_string()))?;
+
+    let no_proxy = no_proxy.to_vec();
+    Ok(Proxy::custom(move |url| match url.host_str() {
+        Some(host) if is_excluded(host, &no_proxy) => None,
+        _ => Some(proxy_url.clone()),
+    }))
+}
+
+/// Matches hosts against a `NO_PROXY` style list, where `*` excludes every
+/// host and a domain excludes itself along with all of its subdomains.
+fn is_excluded(host: &str, no_proxy: &[String]) -> bool {
+    let host = host.to_lowercase();
+    no_proxy.iter().any(|entry| {
+        let entry = entry.trim_start_matches('.');
+        entry == "*"
+            || host == entry
+            || (host.ends_with(entry) && host[..host.len() - entry.len()].ends_with('.'))
+    })
+}
+
+/// Splits a comma separated `NO_PROXY` value into normalised entries
+pub(crate) fn parse_no_proxy(no_proxy: &str) -> Vec<String> {
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_proxy, is_excluded, parse_no_proxy};
+    use crate::client::AirbrakeClientBuilderError;
+
+    #[test]
+    fn proxy_without_scheme_defaults_to_http() {
+        assert!(build_proxy("127.0.0.1:8080", None, &[]).is_ok());
+    }
+
+    #[test]
+    fn proxy_accepts_credentials() {
+        let auth = ("user".to_string(), "p@ss".to_string());
+        assert!(build_proxy("https://proxy.local:3128", Some(&auth), &[]).is_ok());
+    }
+
+    #[test]
+    fn proxy_with_unknown_scheme_is_invalid() {
+        match build_proxy("ftp://proxy.local", None, &[]) {
+            Err(AirbrakeClientBuilderError::InvalidProxy(_)) => (),
+            other => panic!("expected InvalidProxy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn proxy_with_malformed_url_is_invalid() {
+        match build_proxy("http://", None, &[]) {
+            Err(AirbrakeClientBuilderError::InvalidProxy(_)) => (),
+            other => panic!("expected InvalidProxy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_proxy_parses_comma_separated_list() {
+        assert_eq!(
+            vec!["localhost", ".internal.io", "10.0.0.1"],
+            parse_no_proxy(" localhost, .Internal.io,,10.0.0.1 ")
+        );
+    }
+
+    #[test]
+    fn no_proxy_matches_domains_and_subdomains() {
+        let no_proxy = parse_no_proxy("internal.io,localhost");
+        assert!(is_excluded("internal.io", &no_proxy));
+        assert!(is_excluded("api.internal.io", &no_proxy));
+        assert!(is_excluded("LOCALHOST", &no_proxy));
+        assert!(!is_excluded("notinternal.io", &no_proxy));
+        assert!(!is_excluded("app.airbrake.io", &no_proxy));
+    }
+
+    #[test]
+    fn no_proxy_wildcard_matches_everything() {
+        let no_proxy = parse_no_proxy("*");
+        assert!(is_excluded("app.airbrake.io", &no_proxy));
+    }
+}
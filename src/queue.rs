@@ -0,0 +1,200 @@
+use log::warn;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::{AirbrakeClient, AirbrakeClientError, Notice};
+
+/// Counts the notices that were queued but haven't been delivered yet
+#[derive(Debug, Default)]
+struct Pending {
+    count: Mutex<usize>,
+    drained: Condvar,
+}
+
+impl Pending {
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.count.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add(&self) {
+        *self.lock() += 1;
+    }
+
+    fn done(&self) {
+        let mut count = self.lock();
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            self.drained.notify_all();
+        }
+    }
+
+    fn wait(&self, timeout: Duration) -> bool {
+        let count = self.lock();
+        let (count, _) = self
+            .drained
+            .wait_timeout_while(count, timeout, |count| *count > 0)
+            .unwrap_or_else(|e| e.into_inner());
+        *count == 0
+    }
+}
+
+/// Marks a queued notice as handled when dropped, so the count stays right
+/// even when delivering the notice panics
+struct Done<'a>(&'a Pending);
+
+impl Drop for Done<'_> {
+    fn drop(&mut self) {
+        self.0.done();
+    }
+}
+
+/// A bounded queue of notices drained by a pool of worker threads.
+///
+/// Notices are filtered before they are queued. Each worker owns a copy of
+/// the client without a queue of its own and delivers notices
+/// synchronously. The workers shut down once the queue is closed, or every
+/// copy of the client is dropped, and the remaining notices have been
+/// delivered.
+#[derive(Debug)]
+pub(crate) struct NoticeQueue {
+    sender: Mutex<Option<SyncSender<Notice<'static>>>>,
+    pending: Arc<Pending>,
+}
+
+impl NoticeQueue {
+    fn new(sender: SyncSender<Notice<'static>>) -> NoticeQueue {
+        NoticeQueue {
+            sender: Mutex::new(Some(sender)),
+            pending: Arc::default(),
+        }
+    }
+
+    pub(crate) fn start(
+        client: &AirbrakeClient,
+        workers: usize,
+        queue_size: usize,
+    ) -> io::Result<NoticeQueue> {
+        let (sender, receiver) = sync_channel(queue_size);
+        let queue = NoticeQueue::new(sender);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for id in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let pending = Arc::clone(&queue.pending);
+            let client = client.clone();
+            thread::Builder::new()
+                .name(format!("airbrake-worker-{}", id))
+                .spawn(move || NoticeQueue::work(&client, &receiver, &pending))?;
+        }
+        Ok(queue)
+    }
+
+    fn work(
+        client: &AirbrakeClient,
+        receiver: &Mutex<Receiver<Notice<'static>>>,
+        pending: &Pending,
+    ) {
+        loop {
+            // The lock is only held while waiting for the next notice, so
+            // other workers can pick up notices while this one is sending
+            let next = match receiver.lock() {
+                Ok(receiver) => receiver.recv(),
+                Err(_) => return,
+            };
+            let notice = match next {
+                Ok(notice) => notice,
+                Err(_) => return,
+            };
+            let _done = Done(pending);
+            // A panic while delivering, e.g. in a `drop_frames` predicate,
+            // must not take the worker down with it
+            match panic::catch_unwind(AssertUnwindSafe(|| client.deliver(notice))) {
+                Ok(Ok(_)) => (),
+                Ok(Err(e)) => warn!("Airbrake worker failed to deliver notice: {:?}", e),
+                Err(_) => warn!("Airbrake worker panicked while delivering notice"),
+            }
+        }
+    }
+
+    pub(crate) fn push(&self, notice: Notice<'static>) -> Result<(), AirbrakeClientError> {
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        let sender = sender.as_ref().ok_or(AirbrakeClientError::QueueClosed)?;
+        self.pending.add();
+        sender.try_send(notice).map_err(|e| {
+            self.pending.done();
+            match e {
+                TrySendError::Full(_) => AirbrakeClientError::QueueFull,
+                TrySendError::Disconnected(_) => AirbrakeClientError::QueueClosed,
+            }
+        })
+    }
+
+    /// Waits until every queued notice has been delivered, or the timeout
+    /// passes. Returns whether the queue was drained.
+    pub(crate) fn flush(&self, timeout: Duration) -> bool {
+        self.pending.wait(timeout)
+    }
+
+    /// Stops accepting notices, letting the workers exit once the notices
+    /// already queued have been delivered
+    pub(crate) fn close(&self) {
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoticeQueue;
+    use crate::{AirbrakeClientError, Notice};
+    use std::sync::mpsc::sync_channel;
+    use std::time::Duration;
+
+    #[test]
+    fn push_fails_when_queue_is_full() {
+        let (sender, _receiver) = sync_channel(1);
+        let queue = NoticeQueue::new(sender);
+
+        assert!(queue.push(Notice::builder().build()).is_ok());
+        match queue.push(Notice::builder().build()) {
+            Err(AirbrakeClientError::QueueFull) => (),
+            other => panic!("expected QueueFull, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn push_fails_when_workers_are_gone() {
+        let (sender, receiver) = sync_channel(1);
+        drop(receiver);
+        let queue = NoticeQueue::new(sender);
+
+        match queue.push(Notice::builder().build()) {
+            Err(AirbrakeClientError::QueueClosed) => (),
+            other => panic!("expected QueueClosed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn push_fails_after_close() {
+        let (sender, _receiver) = sync_channel(1);
+        let queue = NoticeQueue::new(sender);
+        queue.close();
+
+        match queue.push(Notice::builder().build()) {
+            Err(AirbrakeClientError::QueueClosed) => (),
+            other => panic!("expected QueueClosed, got {:?}", other),
+        }
+        assert!(queue.flush(Duration::from_millis(0)));
+    }
+
+    #[test]
+    fn flush_times_out_while_notices_are_pending() {
+        let (sender, _receiver) = sync_channel(1);
+        let queue = NoticeQueue::new(sender);
+        queue.push(Notice::builder().build()).unwrap();
+
+        assert!(!queue.flush(Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,93 @@
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::AirbrakeClientError;
+
+const RATE_LIMIT_DELAY_HEADER: &str = "X-RateLimit-Delay";
+
+/// Tracks the window during which Airbrake asked us to stop sending
+/// notices. Clones share the same window, so every copy of a client (and
+/// its workers) backs off together.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimit {
+    until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimit {
+    /// Fails with `AirbrakeClientError::RateLimited` while the window is open
+    pub(crate) fn check(&self) -> Result<(), AirbrakeClientError> {
+        let mut until = self.until.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match *until {
+            Some(instant) if instant > now => Err(AirbrakeClientError::RateLimited {
+                retry_after: instant - now,
+            }),
+            Some(_) => {
+                *until = None;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Opens a window that lasts for the given delay
+    pub(crate) fn limit_for(&self, delay: Duration) -> AirbrakeClientError {
+        let mut until = self.until.lock().unwrap_or_else(|e| e.into_inner());
+        *until = Some(Instant::now() + delay);
+        warn!("Airbrake rate limit reached, pausing for {:?}", delay);
+        AirbrakeClientError::RateLimited { retry_after: delay }
+    }
+}
+
+/// Reads the number of seconds Airbrake wants us to wait from a 429 response
+pub(crate) fn rate_limit_delay(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RATE_LIMIT_DELAY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rate_limit_delay, RateLimit};
+    use crate::AirbrakeClientError;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::time::Duration;
+
+    #[test]
+    fn check_passes_without_window() {
+        assert!(RateLimit::default().check().is_ok());
+    }
+
+    #[test]
+    fn check_fails_during_window_across_clones() {
+        let rate_limit = RateLimit::default();
+        let clone = rate_limit.clone();
+        rate_limit.limit_for(Duration::from_secs(60));
+
+        match clone.check() {
+            Err(AirbrakeClientError::RateLimited { retry_after }) => {
+                assert!(retry_after <= Duration::from_secs(60));
+                assert!(retry_after > Duration::from_secs(50));
+            }
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn window_expires() {
+        let rate_limit = RateLimit::default();
+        rate_limit.limit_for(Duration::from_millis(0));
+        assert!(rate_limit.check().is_ok());
+    }
+
+    #[test]
+    fn delay_is_read_from_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, rate_limit_delay(&headers));
+        headers.insert("x-ratelimit-delay", HeaderValue::from_static("42"));
+        assert_eq!(Some(Duration::from_secs(42)), rate_limit_delay(&headers));
+    }
+}
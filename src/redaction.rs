@@ -0,0 +1,237 @@
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::Notice;
+
+const FILTERED: &str = "[Filtered]";
+
+/// Matches the keys of params, session and environment entries, either by
+/// exact name or by regular expression.
+///
+/// ```
+/// use airbrake::KeyPattern;
+/// use regex::Regex;
+///
+/// let exact = KeyPattern::from("password");
+/// let pattern = KeyPattern::from(Regex::new("(?i)token").unwrap());
+/// assert!(exact.matches("password"));
+/// assert!(pattern.matches("X-Auth-Token"));
+/// ```
+#[derive(Debug, Clone)]
+pub enum KeyPattern {
+    Exact(String),
+    Regex(Regex),
+}
+
+impl KeyPattern {
+    pub fn matches(&self, key: &str) -> bool {
+        match self {
+            KeyPattern::Exact(name) => name == key,
+            KeyPattern::Regex(regex) => regex.is_match(key),
+        }
+    }
+}
+
+impl From<&str> for KeyPattern {
+    fn from(key: &str) -> KeyPattern {
+        KeyPattern::Exact(key.to_string())
+    }
+}
+
+impl From<String> for KeyPattern {
+    fn from(key: String) -> KeyPattern {
+        KeyPattern::Exact(key)
+    }
+}
+
+impl From<Regex> for KeyPattern {
+    fn from(regex: Regex) -> KeyPattern {
+        KeyPattern::Regex(regex)
+    }
+}
+
+/// Replaces the values of sensitive keys with `[Filtered]`. A key is
+/// filtered when it matches the blocklist or, once an allowlist has been
+/// configured, when it doesn't match the allowlist.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyRedaction {
+    pub(crate) blocklist: Vec<KeyPattern>,
+    pub(crate) allowlist: Vec<KeyPattern>,
+}
+
+impl KeyRedaction {
+    fn is_filtered(&self, key: &str) -> bool {
+        self.blocklist.iter().any(|pattern| pattern.matches(key))
+            || (!self.allowlist.is_empty()
+                && !self.allowlist.iter().any(|pattern| pattern.matches(key)))
+    }
+
+    fn redact_map(&self, map: &mut HashMap<String, Value>) {
+        for (key, value) in map.iter_mut() {
+            self.redact_entry(key, value);
+        }
+    }
+
+    /// Redacts a value by its key, or the keys nested within it when the
+    /// key itself isn't filtered
+    fn redact_entry(&self, key: &str, value: &mut Value) {
+        if self.is_filtered(key) {
+            *value = Value::from(FILTERED);
+        } else {
+            self.redact_nested(value);
+        }
+    }
+
+    fn redact_nested(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    self.redact_entry(key, value);
+                }
+            }
+            Value::Array(values) => {
+                for value in values.iter_mut() {
+                    self.redact_nested(value);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn redact_url(&self, url: &str) -> String {
+        let (base, fragment) = match url.find('#') {
+            Some(index) => url.split_at(index),
+            None => (url, ""),
+        };
+        let (path, query) = match base.find('?') {
+            Some(index) => (&base[..index], &base[index + 1..]),
+            None => return url.to_string(),
+        };
+        let query: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.find('=') {
+                Some(index) if self.is_filtered(&pair[..index]) => {
+                    format!("{}={}", &pair[..index], FILTERED)
+                }
+                _ => pair.to_string(),
+            })
+            .collect();
+        format!("{}?{}{}", path, query.join("&"), fragment)
+    }
+
+    pub(crate) fn apply(&self, notice: &mut Notice<'_>) {
+        if self.blocklist.is_empty() && self.allowlist.is_empty() {
+            return;
+        }
+        for map in vec![
+            notice.params.as_mut(),
+            notice.session.as_mut(),
+            notice.environment.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.redact_map(map);
+        }
+        if let Some(context) = notice.context.as_mut() {
+            context.url = context.url.as_ref().map(|url| self.redact_url(url));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyPattern, KeyRedaction};
+    use crate::{ContextProperties, Notice};
+    use regex::Regex;
+    use serde_json::json;
+
+    fn redaction(blocklist: Vec<KeyPattern>, allowlist: Vec<KeyPattern>) -> KeyRedaction {
+        KeyRedaction {
+            blocklist,
+            allowlist,
+        }
+    }
+
+    #[test]
+    fn blocklist_filters_all_maps() {
+        let redaction = redaction(vec!["password".into()], vec![]);
+        let mut notice = Notice::builder()
+            .add_param("password", "hunter2")
+            .add_param("page", "3")
+            .add_session("password", "hunter2")
+            .add_environment("password", "hunter2")
+            .build();
+        redaction.apply(&mut notice);
+
+        let params = notice.params.unwrap();
+        assert_eq!("[Filtered]", params["password"]);
+        assert_eq!("3", params["page"]);
+        assert_eq!("[Filtered]", notice.session.unwrap()["password"]);
+        assert_eq!("[Filtered]", notice.environment.unwrap()["password"]);
+    }
+
+    #[test]
+    fn blocklist_filters_nested_values() {
+        let redaction = redaction(vec!["password".into()], vec![]);
+        let mut notice = Notice::builder()
+            .add_param_value("users", &json!([{"name": "bob", "password": "hunter2"}]))
+            .build();
+        redaction.apply(&mut notice);
+
+        assert_eq!(
+            json!([{"name": "bob", "password": "[Filtered]"}]),
+            notice.params.unwrap()["users"]
+        );
+    }
+
+    #[test]
+    fn blocklist_matches_regex() {
+        let token = Regex::new("(?i)token$").unwrap();
+        let redaction = redaction(vec![token.into()], vec![]);
+        let mut notice = Notice::builder()
+            .add_param("accessToken", "abc")
+            .add_param("tokenizer", "whitespace")
+            .build();
+        redaction.apply(&mut notice);
+
+        let params = notice.params.unwrap();
+        assert_eq!("[Filtered]", params["accessToken"]);
+        assert_eq!("whitespace", params["tokenizer"]);
+    }
+
+    #[test]
+    fn allowlist_filters_everything_else() {
+        let redaction = redaction(vec![], vec!["page".into()]);
+        let mut notice = Notice::builder()
+            .add_param("page", "3")
+            .add_param("sort", "name")
+            .build();
+        redaction.apply(&mut notice);
+
+        let params = notice.params.unwrap();
+        assert_eq!("3", params["page"]);
+        assert_eq!("[Filtered]", params["sort"]);
+    }
+
+    #[test]
+    fn blocklist_filters_url_query() {
+        let redaction = redaction(vec!["token".into()], vec![]);
+        let mut notice = Notice::builder()
+            .url("https://example.com/login?user=bob&token=abc&flag#top")
+            .build();
+        redaction.apply(&mut notice);
+
+        assert_eq!(
+            Some("https://example.com/login?user=bob&token=[Filtered]&flag#top".to_string()),
+            notice.context.unwrap().url
+        );
+    }
+
+    #[test]
+    fn url_without_query_is_untouched() {
+        let redaction = redaction(vec!["token".into()], vec![]);
+        assert_eq!("/login#token=abc", redaction.redact_url("/login#token=abc"));
+    }
+}
@@ -0,0 +1,115 @@
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::AirbrakeClientError;
+
+/// The notice Airbrake created in response to a successful notification
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NoticeResponse {
+    /// The id Airbrake assigned to the notice
+    pub id: String,
+    /// A link to the notice on the Airbrake dashboard
+    pub url: String,
+}
+
+/// Interprets the status and body of a v3 create notice response
+pub(crate) fn parse_response(
+    status: StatusCode,
+    body: &str,
+) -> Result<NoticeResponse, AirbrakeClientError> {
+    if status.is_success() {
+        return serde_json::from_str(body)
+            .map_err(|e| AirbrakeClientError::InvalidResponse(e.to_string()));
+    }
+
+    let message = error_message(body);
+    let status = status.as_u16();
+    Err(match status {
+        400 | 422 => AirbrakeClientError::BadRequest { status, message },
+        401 | 403 => AirbrakeClientError::Unauthorized { status, message },
+        429 => AirbrakeClientError::TooManyRequests { status, message },
+        500..=599 => AirbrakeClientError::ServerError { status, message },
+        _ => AirbrakeClientError::UnexpectedStatus { status, message },
+    })
+}
+
+/// Airbrake reports errors as `{"message": "..."}`, but proxies in front
+/// of it may answer with anything, so fall back to the raw body
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|json| {
+            json.get("message")
+                .or_else(|| json.get("error"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_response, NoticeResponse};
+    use crate::AirbrakeClientError;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn created_response_returns_notice() {
+        let body = r#"{"id": "12345", "url": "https://airbrake.io/locate/12345"}"#;
+        let response = parse_response(StatusCode::CREATED, body).unwrap();
+        assert_eq!(
+            NoticeResponse {
+                id: "12345".to_string(),
+                url: "https://airbrake.io/locate/12345".to_string(),
+            },
+            response
+        );
+    }
+
+    #[test]
+    fn malformed_success_body_is_invalid() {
+        match parse_response(StatusCode::CREATED, "<html></html>") {
+            Err(AirbrakeClientError::InvalidResponse(_)) => (),
+            other => panic!("expected InvalidResponse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unauthorized_response_carries_server_message() {
+        let body = r#"{"message": "Project API key is invalid"}"#;
+        match parse_response(StatusCode::UNAUTHORIZED, body) {
+            Err(AirbrakeClientError::Unauthorized { status, message }) => {
+                assert_eq!(401, status);
+                assert_eq!("Project API key is invalid", message);
+            }
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_statuses_map_to_distinct_variants() {
+        match parse_response(StatusCode::BAD_REQUEST, "{}") {
+            Err(AirbrakeClientError::BadRequest { status: 400, .. }) => (),
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+        match parse_response(StatusCode::TOO_MANY_REQUESTS, "{}") {
+            Err(AirbrakeClientError::TooManyRequests { status: 429, .. }) => (),
+            other => panic!("expected TooManyRequests, got {:?}", other),
+        }
+        match parse_response(StatusCode::NOT_FOUND, "{}") {
+            Err(AirbrakeClientError::UnexpectedStatus { status: 404, .. }) => (),
+            other => panic!("expected UnexpectedStatus, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn server_error_falls_back_to_raw_body() {
+        match parse_response(StatusCode::BAD_GATEWAY, "upstream timed out\n") {
+            Err(AirbrakeClientError::ServerError { status, message }) => {
+                assert_eq!(502, status);
+                assert_eq!("upstream timed out", message);
+            }
+            other => panic!("expected ServerError, got {:?}", other),
+        }
+    }
+}
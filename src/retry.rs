@@ -0,0 +1,136 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::AirbrakeClientError;
+
+const DEFAULT_MAX_RETRIES: u32 = 0;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Decides whether a failed request is retried and how long to wait first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the backoff to wait before retrying after the given attempt,
+    /// or `None` when the error is final or the retries are used up
+    pub(crate) fn next_backoff(
+        &self,
+        attempt: u32,
+        error: &AirbrakeClientError,
+    ) -> Option<Duration> {
+        if attempt > self.max_retries || !RetryPolicy::is_retryable(error) {
+            return None;
+        }
+        Some(self.backoff(attempt))
+    }
+
+    /// Only failures that are likely to be transient are worth retrying.
+    /// Rejected notices will be rejected again, and rate limits have their
+    /// own backoff window.
+    fn is_retryable(error: &AirbrakeClientError) -> bool {
+        match error {
+            AirbrakeClientError::ReqwestError(e) => e.is_connect() || e.is_timeout(),
+            AirbrakeClientError::ServerError { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Exponential backoff with "equal jitter": half of the exponential
+    /// delay is always waited, the other half is random, so concurrent
+    /// workers don't retry in lockstep
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << exponent)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        let half = backoff / 2;
+        let jitter_millis = half.as_millis() as u64;
+        let jitter = match jitter_millis {
+            0 => Duration::from_millis(0),
+            max => Duration::from_millis(rand::thread_rng().gen_range(0, max + 1)),
+        };
+        half + jitter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::AirbrakeClientError;
+    use std::time::Duration;
+
+    fn server_error() -> AirbrakeClientError {
+        AirbrakeClientError::ServerError {
+            status: 500,
+            message: String::new(),
+        }
+    }
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+        }
+    }
+
+    #[test]
+    fn default_policy_does_not_retry() {
+        assert_eq!(
+            None,
+            RetryPolicy::default().next_backoff(1, &server_error())
+        );
+    }
+
+    #[test]
+    fn retries_server_errors_until_exhausted() {
+        let policy = policy(2);
+        assert!(policy.next_backoff(1, &server_error()).is_some());
+        assert!(policy.next_backoff(2, &server_error()).is_some());
+        assert_eq!(None, policy.next_backoff(3, &server_error()));
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let error = AirbrakeClientError::BadRequest {
+            status: 400,
+            message: String::new(),
+        };
+        assert_eq!(None, policy(3).next_backoff(1, &error));
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_with_jitter() {
+        let policy = policy(10);
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(3);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let backoff = policy(100).backoff(40);
+        assert!(backoff <= Duration::from_millis(1000));
+        assert!(backoff >= Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,252 @@
+use std::cell::RefCell;
+#[cfg(feature = "async")]
+use std::future::Future;
+
+use crate::{ContextBuilder, ContextProperties, NoticeBuilder};
+
+thread_local! {
+    static THREAD_SCOPE: RefCell<Option<ContextBuilder>> = const { RefCell::new(None) };
+}
+
+#[cfg(feature = "async")]
+tokio::task_local! {
+    static TASK_SCOPE: RefCell<Option<ContextBuilder>>;
+}
+
+/// Request-specific context, such as the user and route, that is merged
+/// into every notice built with `new_notice_builder` while the scope is
+/// active. A scope starts out with the properties of the enclosing scope.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    context: ContextBuilder,
+}
+
+impl ContextProperties for Scope {
+    fn get_context(&self) -> Option<ContextBuilder> {
+        Some(self.context.clone())
+    }
+
+    fn set_context(&mut self, context: &ContextBuilder) -> &mut Self {
+        self.context = context.clone();
+        self
+    }
+}
+
+impl Scope {
+    fn configure<C: FnOnce(&mut Scope)>(configure: C) -> Scope {
+        let mut scope = Scope {
+            context: current_scope().unwrap_or_default(),
+        };
+        configure(&mut scope);
+        scope
+    }
+}
+
+/// Runs `f` with a scope configured by `configure`. Within an async task
+/// scoped by `with_scope_async`, the task's scope is extended instead of
+/// the thread's.
+///
+/// ```
+/// use airbrake::{ContextProperties, ContextUser};
+///
+/// let airbrake = airbrake::configure(|config| {
+///     config.project_id("113743");
+///     config.project_key("81bbff95d52f8856c770bb39e827f3f6");
+/// });
+///
+/// airbrake::with_scope(
+///     |scope| {
+///         scope.user(ContextUser::default().id("42")).route("/checkout");
+///     },
+///     || {
+///         let notice = airbrake.new_notice_builder().build();
+///         assert_eq!(Some("/checkout"), notice.context.unwrap().route.as_deref());
+///     },
+/// );
+/// ```
+pub fn with_scope<C, F, R>(configure: C, f: F) -> R
+where
+    C: FnOnce(&mut Scope),
+    F: FnOnce() -> R,
+{
+    let scope = Scope::configure(configure);
+    let _guard = ScopeGuard {
+        previous: replace_current(Some(scope.context)),
+    };
+    f()
+}
+
+/// Runs the future with a scope configured by `configure`. The scope is
+/// stored in a tokio task-local, so it follows the future across the
+/// threads of the runtime.
+#[cfg(feature = "async")]
+pub fn with_scope_async<C, F>(configure: C, future: F) -> impl Future<Output = F::Output>
+where
+    C: FnOnce(&mut Scope),
+    F: Future,
+{
+    let scope = Scope::configure(configure);
+    TASK_SCOPE.scope(RefCell::new(Some(scope.context)), future)
+}
+
+/// Restores the enclosing scope when `with_scope` returns or unwinds
+struct ScopeGuard {
+    previous: Option<ContextBuilder>,
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        replace_current(self.previous.take());
+    }
+}
+
+fn replace_current(context: Option<ContextBuilder>) -> Option<ContextBuilder> {
+    #[cfg(feature = "async")]
+    {
+        if let Ok(previous) = TASK_SCOPE.try_with(|scope| scope.replace(context.clone())) {
+            return previous;
+        }
+    }
+    THREAD_SCOPE.with(|scope| scope.replace(context))
+}
+
+/// The innermost active scope, preferring the current task's over the
+/// current thread's
+pub(crate) fn current_scope() -> Option<ContextBuilder> {
+    #[cfg(feature = "async")]
+    {
+        if let Ok(context) = TASK_SCOPE.try_with(|scope| scope.borrow().clone()) {
+            return context;
+        }
+    }
+    THREAD_SCOPE.with(|scope| scope.borrow().clone())
+}
+
+/// Merges the active scope into the notice's context
+pub(crate) fn apply_scope(notice_builder: &mut NoticeBuilder<'_>) {
+    if let Some(scope) = current_scope() {
+        notice_builder
+            .context
+            .get_or_insert_with(ContextBuilder::new)
+            .merge(&scope);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{current_scope, with_scope};
+    use crate::{AirbrakeClient, ContextProperties, ContextUser};
+
+    fn client() -> AirbrakeClient {
+        AirbrakeClient::builder()
+            .project("foo", "bar")
+            .environment("production")
+            .route("/default")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn scope_is_merged_into_client_context() {
+        let client = client();
+        let context = with_scope(
+            |scope| {
+                scope
+                    .user(ContextUser::default().id("42"))
+                    .route("/checkout");
+            },
+            || client.new_notice_builder().build().context.unwrap(),
+        );
+
+        assert_eq!(Some("production"), context.environment.as_deref());
+        assert_eq!(Some("/checkout"), context.route.as_deref());
+        assert_eq!(Some(ContextUser::default().id("42")), context.user);
+    }
+
+    #[test]
+    fn nested_scopes_extend_and_restore_the_enclosing_scope() {
+        let client = client();
+        with_scope(
+            |scope| {
+                scope.http_method("POST");
+            },
+            || {
+                let context = with_scope(
+                    |scope| {
+                        scope.route("/inner");
+                    },
+                    || client.new_notice_builder().build().context.unwrap(),
+                );
+                assert_eq!(Some("POST"), context.http_method.as_deref());
+                assert_eq!(Some("/inner"), context.route.as_deref());
+
+                let context = client.new_notice_builder().build().context.unwrap();
+                assert_eq!(Some("/default"), context.route.as_deref());
+            },
+        );
+
+        assert!(current_scope().is_none());
+    }
+
+    #[test]
+    fn scope_is_restored_after_a_panic() {
+        let _lock = crate::panic::HOOK_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let result = std::panic::catch_unwind(|| {
+            with_scope(
+                |scope| {
+                    scope.route("/panics");
+                },
+                || panic!("boom"),
+            )
+        });
+
+        assert!(result.is_err());
+        assert!(current_scope().is_none());
+    }
+
+    #[test]
+    fn scopes_are_local_to_their_thread() {
+        with_scope(
+            |scope| {
+                scope.route("/checkout");
+            },
+            || {
+                let other = std::thread::spawn(current_scope).join().unwrap();
+                assert!(other.is_none());
+            },
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[actix_rt::test]
+    async fn async_scope_follows_the_task() {
+        let client = AirbrakeClient::builder()
+            .project("foo", "bar")
+            .build_async()
+            .unwrap();
+        let route = super::with_scope_async(
+            |scope| {
+                scope.route("/async");
+            },
+            async {
+                tokio::time::delay_for(std::time::Duration::from_millis(1)).await;
+                with_scope(
+                    |scope| {
+                        scope.http_method("GET");
+                    },
+                    || {
+                        let context = client.new_notice_builder().build().context.unwrap();
+                        assert_eq!(Some("GET"), context.http_method.as_deref());
+                    },
+                );
+                client.new_notice_builder().build().context.unwrap().route
+            },
+        )
+        .await;
+
+        assert_eq!(Some("/async".to_string()), route);
+        assert!(current_scope().is_none());
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// The severity levels understood by the Airbrake dashboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+    Invalid,
+    /// Any other severity string, sent to Airbrake as is
+    Custom(String),
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Notice => "notice",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+            Severity::Alert => "alert",
+            Severity::Emergency => "emergency",
+            Severity::Invalid => "invalid",
+            Severity::Custom(severity) => severity,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Maps log levels onto the closest severity, `Trace` is reported as
+/// `Debug` since Airbrake has nothing lower
+impl From<log::Level> for Severity {
+    fn from(level: log::Level) -> Severity {
+        match level {
+            log::Level::Error => Severity::Error,
+            log::Level::Warn => Severity::Warning,
+            log::Level::Info => Severity::Info,
+            log::Level::Debug | log::Level::Trace => Severity::Debug,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Severity;
+    use serde_json::{self, Value};
+
+    #[test]
+    fn severity_serializes_to_airbrake_strings() {
+        assert_eq!(
+            Value::from("critical"),
+            serde_json::json!(Severity::Critical)
+        );
+        assert_eq!(Value::from("warning"), serde_json::json!(Severity::Warning));
+        assert_eq!(
+            Value::from("fatal"),
+            serde_json::json!(Severity::Custom("fatal".to_string()))
+        );
+    }
+
+    #[test]
+    fn severity_from_log_level() {
+        assert_eq!(Severity::Error, log::Level::Error.into());
+        assert_eq!(Severity::Warning, log::Level::Warn.into());
+        assert_eq!(Severity::Info, log::Level::Info.into());
+        assert_eq!(Severity::Debug, log::Level::Trace.into());
+    }
+}